@@ -0,0 +1,29 @@
+extern crate ruskell;
+use ruskell::json;
+use std::hint::black_box;
+use std::time::Instant;
+
+#[path = "json/handwritten.rs"]
+mod handwritten;
+
+use handwritten::{Handwritten, sample};
+
+// Runs f until a second has passed and prints the throughput, like the bench harness does.
+fn bench<F, R>(name:&str, bytes:usize, f:F) where F:Fn()->R {
+    let start = Instant::now();
+    let mut rounds = 0;
+    while rounds == 0 || start.elapsed().as_secs() < 1 {
+        black_box(f());
+        rounds += 1;
+    }
+    let elapsed = start.elapsed();
+    let per_round = elapsed / rounds;
+    let mb = (bytes as f64) * (rounds as f64) / elapsed.as_secs_f64() / 1e6;
+    println!("{:20} {:>12?}/iter {:>8.2} MB/s", name, per_round, mb);
+}
+
+fn main() {
+    let doc = sample();
+    bench("json_combinators", doc.len(), || json::parse(&doc).unwrap());
+    bench("json_handwritten", doc.len(), || Handwritten::parse(&doc).unwrap());
+}
@@ -0,0 +1,188 @@
+use ruskell::json::Value;
+use std::collections::BTreeMap;
+
+// A plain recursive descent parser for the same Value, as the baseline the combinator
+// version is measured against.
+pub struct Handwritten<'a> {
+    text: &'a str,
+    data: &'a [u8],
+    index: usize,
+}
+
+impl<'a> Handwritten<'a> {
+    pub fn parse(text:&'a str)->Option<Value> {
+        let mut p = Handwritten{text:text, data:text.as_bytes(), index:0};
+        p.ws();
+        let re = p.value();
+        p.ws();
+        if p.index == p.data.len() { re } else { None }
+    }
+
+    fn peek(&self)->Option<u8> {
+        self.data.get(self.index).cloned()
+    }
+
+    fn ws(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.index += 1;
+        }
+    }
+
+    fn eat(&mut self, c:u8)->Option<()> {
+        if self.peek() == Some(c) {
+            self.index += 1;
+            self.ws();
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn literal(&mut self, word:&str, value:Value)->Option<Value> {
+        if self.data[self.index..].starts_with(word.as_bytes()) {
+            self.index += word.len();
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    fn value(&mut self)->Option<Value> {
+        let re = match self.peek() {
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'[') => self.array(),
+            Some(b'{') => self.object(),
+            _ => self.number(),
+        };
+        self.ws();
+        re
+    }
+
+    fn number(&mut self)->Option<Value> {
+        let start = self.index;
+        if self.peek() == Some(b'-') {
+            self.index += 1;
+        }
+        match self.peek() {
+            Some(b'0') => self.index += 1,
+            Some(b'1'..=b'9') => self.digits(),
+            _ => return None,
+        }
+        if self.peek() == Some(b'.') {
+            self.index += 1;
+            if !self.peek().map_or(false, |c| c.is_ascii_digit()) {
+                return None;
+            }
+            self.digits();
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            self.index += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.index += 1;
+            }
+            if !self.peek().map_or(false, |c| c.is_ascii_digit()) {
+                return None;
+            }
+            self.digits();
+        }
+        self.text[start..self.index].parse::<f64>().ok().map(Value::Number)
+    }
+
+    fn digits(&mut self) {
+        while self.peek().map_or(false, |c| c.is_ascii_digit()) {
+            self.index += 1;
+        }
+    }
+
+    fn string(&mut self)->Option<String> {
+        self.index += 1;
+        let mut units = Vec::new();
+        loop {
+            let c = match self.text[self.index..].chars().next() {
+                Some(c) => c,
+                None => return None,
+            };
+            self.index += c.len_utf8();
+            match c {
+                '"' => return Some(String::from_utf16_lossy(&units)),
+                '\\' => {
+                    let e = match self.peek() {
+                        Some(e) => e,
+                        None => return None,
+                    };
+                    self.index += 1;
+                    units.push(match e {
+                        b'"' => 0x22, b'\\' => 0x5c, b'/' => 0x2f,
+                        b'b' => 0x08, b'f' => 0x0c, b'n' => 0x0a, b'r' => 0x0d, b't' => 0x09,
+                        b'u' => {
+                            let hex = match self.data.get(self.index..self.index + 4) {
+                                Some(hex) => ::std::str::from_utf8(hex).unwrap(),
+                                None => return None,
+                            };
+                            self.index += 4;
+                            match u16::from_str_radix(hex, 16) {
+                                Ok(unit) => unit,
+                                Err(_) => return None,
+                            }
+                        }
+                        _ => return None,
+                    });
+                }
+                c if (c as u32) < 0x20 => return None,
+                c => {
+                    let mut buf = [0u16; 2];
+                    units.extend_from_slice(c.encode_utf16(&mut buf));
+                }
+            }
+        }
+    }
+
+    fn array(&mut self)->Option<Value> {
+        self.eat(b'[')?;
+        let mut items = Vec::new();
+        if self.eat(b']').is_some() {
+            return Some(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            if self.eat(b',').is_none() {
+                break;
+            }
+        }
+        self.eat(b']')?;
+        Some(Value::Array(items))
+    }
+
+    fn object(&mut self)->Option<Value> {
+        self.eat(b'{')?;
+        let mut members = BTreeMap::new();
+        if self.eat(b'}').is_some() {
+            return Some(Value::Object(members));
+        }
+        loop {
+            if self.peek() != Some(b'"') {
+                return None;
+            }
+            let key = self.string()?;
+            self.ws();
+            self.eat(b':')?;
+            let value = self.value()?;
+            members.insert(key, value);
+            if self.eat(b',').is_none() {
+                break;
+            }
+        }
+        self.eat(b'}')?;
+        Some(Value::Object(members))
+    }
+}
+
+pub fn sample()->String {
+    let items = (0..200).map(|i| format!(
+        "{{\"id\": {}, \"name\": \"item \\u00e9 {}\", \"price\": {}.5e-1, \"tags\": [\"a\", \"b\", null], \"ok\": {}}}",
+        i, i, i * 3, i % 2 == 0)).collect::<Vec<String>>();
+    format!("{{\"items\": [{}], \"count\": 200}}", items.join(",\n  "))
+}
@@ -0,0 +1,93 @@
+#![no_main]
+// The repetition combinators over arbitrary text. A failed try must leave the position where
+// it started, a success must consume exactly what it returns. Loops that never end show up as
+// libFuzzer timeouts, so many, sep_by and many_till also run over parsers that may match
+// nothing.
+use libfuzzer_sys::fuzz_target;
+use ruskell::parsec::atom::{eq, none_of, one, one_of, pack, string};
+use ruskell::parsec::combinator::{between, either, look_ahead, many, many1, many_till, r#try, sep_by, skip};
+use ruskell::parsec::text::digit;
+use ruskell::parsec::{Parsec, Parser, State, VecState};
+use std::iter::FromIterator;
+
+// Runs try(p) at from and returns the consumed chars with the result, None on failure.
+fn run<R: Clone + 'static>(p: &Parser<char, R, usize, usize>, chars: &[char], from: usize) -> Option<(String, R)> {
+    let mut state = VecState::from_iter(chars.iter().cloned());
+    assert!(state.seek_to(from));
+    match r#try(p.clone()).parse(&mut state) {
+        Ok(x) => {
+            assert!(state.pos() >= from);
+            Some((chars[from..state.pos()].iter().collect(), x))
+        }
+        Err(_) => {
+            assert_eq!(state.pos(), from);
+            None
+        }
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(_) => return,
+    };
+    let chars = text.chars().collect::<Vec<char>>();
+    let ab: Parser<char, Vec<char>, usize, usize> = many(one_of("ab"));
+    let numbers: Parser<char, Vec<Vec<char>>, usize, usize> = sep_by(many1(digit()), eq(','));
+    let statement: Parser<char, Vec<char>, usize, usize> = many_till(one(), eq(';'));
+    let group: Parser<char, Vec<char>, usize, usize> = between(eq('('), eq(')'), many(none_of(")")));
+    let word: Parser<char, String, usize, usize> = string("ab");
+    let optional: Parser<char, char, usize, usize> = either(r#try(eq('a')), pack('-'));
+    let maybe_a: Parser<char, Vec<char>, usize, usize> = many(optional.clone());
+    let maybe_a1: Parser<char, Vec<char>, usize, usize> = many1(optional.clone());
+    let skip_a: Parser<char, Vec<char>, usize, usize> = skip(optional.clone());
+    let comma: Parser<char, char, usize, usize> = either(r#try(eq(',')), pack(' '));
+    let maybe_list: Parser<char, Vec<char>, usize, usize> = sep_by(optional.clone(), comma);
+    let maybe_statement: Parser<char, Vec<char>, usize, usize> = many_till(optional, eq(';'));
+    let peek: Parser<char, Vec<char>, usize, usize> = many(look_ahead(one()));
+    for from in 0..(chars.len() + 1).min(32) {
+        let (consumed, x) = run(&ab, &chars, from).unwrap();
+        assert_eq!(consumed, x.iter().collect::<String>());
+        assert!(!chars[from..].get(x.len()).map_or(false, |c| *c == 'a' || *c == 'b'));
+
+        let (consumed, x) = run(&numbers, &chars, from).unwrap();
+        let joined = x.iter().map(|n| n.iter().collect::<String>()).collect::<Vec<String>>().join(",");
+        assert_eq!(consumed, joined);
+
+        if let Some((consumed, x)) = run(&statement, &chars, from) {
+            assert_eq!(consumed, format!("{};", x.iter().collect::<String>()));
+            assert!(!x.contains(&';'));
+        }
+
+        if let Some((consumed, x)) = run(&group, &chars, from) {
+            assert_eq!(consumed, format!("({})", x.iter().collect::<String>()));
+        }
+
+        assert_eq!(run(&word, &chars, from).is_some(), chars[from..].starts_with(&['a', 'b']));
+
+        // The round that matches nothing ends the loop and is the last item.
+        let a_count = chars[from..].iter().take_while(|c| **c == 'a').count();
+        for p in &[&maybe_a, &maybe_a1] {
+            let (consumed, x) = run(p, &chars, from).unwrap();
+            assert_eq!(x.last(), Some(&'-'));
+            assert_eq!(consumed, x[..x.len() - 1].iter().collect::<String>());
+            assert_eq!(consumed.len(), a_count);
+        }
+        assert_eq!(run(&skip_a, &chars, from).unwrap().0.len(), a_count);
+
+        let (consumed, x) = run(&maybe_list, &chars, from).unwrap();
+        assert!(!x.is_empty());
+        assert!(consumed.chars().all(|c| c == 'a' || c == ','));
+        assert_eq!(x.iter().filter(|c| **c == 'a').count(), consumed.chars().filter(|c| *c == 'a').count());
+
+        // Either every item consumed an a and the ; follows, or no progress stops it with an error.
+        if let Some((consumed, x)) = run(&maybe_statement, &chars, from) {
+            assert_eq!(consumed, format!("{};", x.iter().collect::<String>()));
+            assert!(x.iter().all(|c| *c == 'a'));
+        }
+
+        let (consumed, x) = run(&peek, &chars, from).unwrap();
+        assert_eq!(consumed, "");
+        assert_eq!(x, chars.get(from).cloned().into_iter().collect::<Vec<char>>());
+    }
+});
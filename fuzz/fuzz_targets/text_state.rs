@@ -0,0 +1,79 @@
+#![no_main]
+// StringState indexes bytes and VecState<char> indexes chars. Both run the same script of
+// State calls and the same text parsers in lockstep and must agree at every step.
+use libfuzzer_sys::fuzz_target;
+use ruskell::parsec::combinator::{many, sep_by};
+use ruskell::parsec::atom::eq;
+use ruskell::parsec::text::{alpha, float, integer, newline, uinteger, StringState};
+use ruskell::parsec::{Parsec, Parser, State, VecState};
+use std::fmt::Debug;
+use std::iter::FromIterator;
+
+// Byte offset of every char index, and of the end.
+fn offsets(text: &str) -> Vec<usize> {
+    text.char_indices().map(|(i, _)| i).chain(Some(text.len())).collect()
+}
+
+fn same<R: Clone + PartialEq + Debug>(p: &Parser<char, R, usize, usize>, text: &str, from: usize) {
+    let at = offsets(text);
+    let mut bytes = StringState::new(String::from(text));
+    let mut chars = VecState::from_iter(text.chars());
+    assert!(bytes.seek_to(at[from]));
+    assert!(chars.seek_to(from));
+    let x = p.parse(&mut bytes);
+    let y = p.parse(&mut chars);
+    assert_eq!(x.is_ok(), y.is_ok());
+    if let (Ok(x), Ok(y)) = (x, y) {
+        assert_eq!(x, y);
+    }
+    assert_eq!(bytes.pos(), at[chars.pos()]);
+}
+
+fuzz_target!(|data: &[u8]| {
+    let (script, text) = data.split_at(data.len().min(16));
+    let text = match std::str::from_utf8(text) {
+        Ok(text) => text,
+        Err(_) => return,
+    };
+    let at = offsets(text);
+    let mut bytes = StringState::new(String::from(text));
+    let mut chars = VecState::from_iter(text.chars());
+    let mut trans = Vec::new();
+    for op in script {
+        match op % 5 {
+            0 => assert_eq!(bytes.next(), chars.next()),
+            1 => {
+                let x = bytes.next_by(&|c: &char| c.is_alphabetic());
+                let y = chars.next_by(&|c: &char| c.is_alphabetic());
+                assert_eq!(x.ok(), y.ok());
+            }
+            2 => trans.push((bytes.begin(), chars.begin())),
+            3 => {
+                if let Some((x, y)) = trans.pop() {
+                    bytes.rollback(x);
+                    chars.rollback(y);
+                }
+            }
+            _ => {
+                let to = (op / 5) as usize % at.len();
+                assert!(bytes.seek_to(at[to]));
+                assert!(chars.seek_to(to));
+                // Inside a multibyte char is not a position of StringState.
+                if to + 1 < at.len() && at[to + 1] - at[to] > 1 {
+                    assert!(!bytes.seek_to(at[to] + 1));
+                }
+            }
+        }
+        assert_eq!(bytes.pos(), at[chars.pos()]);
+    }
+    let lines: Parser<char, Vec<String>, usize, usize> = sep_by(integer(), newline());
+    for from in 0..(at.len()).min(8) {
+        same(&integer(), text, from);
+        same(&uinteger(), text, from);
+        same(&float(), text, from);
+        same(&many(alpha()), text, from);
+        same(&newline(), text, from);
+        same(&eq('-'), text, from);
+        same(&lines, text, from);
+    }
+});
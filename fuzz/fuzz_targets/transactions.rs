@@ -0,0 +1,87 @@
+#![no_main]
+// begin/commit/rollback nested in any order. VecState and StreamState run the same script and
+// must agree; rolling back returns to where the transaction began, even after StreamState has
+// dropped input, and nested transactions inside try and look_ahead leave no trace.
+use libfuzzer_sys::fuzz_target;
+use ruskell::parsec::atom::{eq, one};
+use ruskell::parsec::combinator::{either, look_ahead, many, not_followed_by, r#try};
+use ruskell::parsec::stream::StreamState;
+use ruskell::parsec::{Monad, Parsec, Parser, State, VecState};
+use std::fmt::Debug;
+use std::iter::FromIterator;
+
+// p agrees on both states from every start, and a failure consumes nothing.
+fn same<R: Clone + PartialEq + Debug + 'static>(p: &Parser<u8, R, usize, usize>, input: &[u8]) {
+    for from in 0..(input.len() + 1).min(16) {
+        let mut vec = VecState::from_iter(input.iter().cloned());
+        let mut stream = StreamState::new(input.to_vec().into_iter());
+        assert!(vec.seek_to(from));
+        assert!(stream.seek_to(from));
+        let tran = vec.begin();
+        let x = p.parse(&mut vec);
+        let y = p.parse(&mut stream);
+        assert_eq!(x.is_ok(), y.is_ok());
+        match (x, y) {
+            (Ok(x), Ok(y)) => assert_eq!(x, y),
+            _ => assert_eq!(vec.pos(), from),
+        }
+        assert_eq!(vec.pos(), stream.pos());
+        vec.rollback(tran);
+        assert_eq!(vec.pos(), from);
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    if data.is_empty() {
+        return;
+    }
+    let len = (data[0] as usize).min(data.len() - 1);
+    let (input, script) = data[1..].split_at(len);
+    let mut vec = VecState::from_iter(input.iter().cloned());
+    let mut stream = StreamState::new(input.to_vec().into_iter());
+    let mut trans: Vec<usize> = Vec::new();
+    for op in script {
+        let pos = vec.pos();
+        match op % 5 {
+            0 => assert_eq!(vec.next(), stream.next()),
+            1 => {
+                let x = vec.begin();
+                assert_eq!(x, stream.begin());
+                trans.push(x);
+            }
+            2 => {
+                if let Some(tran) = trans.pop() {
+                    vec.commit(tran);
+                    stream.commit(tran);
+                    assert_eq!(vec.pos(), pos);
+                }
+            }
+            3 => {
+                if let Some(tran) = trans.pop() {
+                    vec.rollback(tran);
+                    stream.rollback(tran);
+                    assert_eq!(vec.pos(), tran);
+                }
+            }
+            _ => {
+                // Back to any open transaction start, or forward, works on both states.
+                let to = match trans.first() {
+                    Some(first) => first + (op / 5) as usize % (input.len() + 1 - first),
+                    None => pos + (op / 5) as usize % (input.len() + 1 - pos),
+                };
+                assert!(vec.seek_to(to));
+                assert!(stream.seek_to(to));
+            }
+        }
+        assert_eq!(vec.pos(), stream.pos());
+        if trans.is_empty() {
+            assert!(stream.buffered() <= input.len() - stream.pos());
+        }
+    }
+
+    let a: Parser<u8, u8, usize, usize> = eq(b'a');
+    let b: Parser<u8, u8, usize, usize> = eq(b'b');
+    let pair = r#try(a.clone().then(b.clone()));
+    same(&either(r#try(many(pair).then(a.clone())), look_ahead(one())), input);
+    same(&not_followed_by(r#try(many(r#try(a)).then(b))).then(many(one())), input);
+});
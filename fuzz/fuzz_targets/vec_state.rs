@@ -0,0 +1,67 @@
+#![no_main]
+// A script of State calls on VecState<u8>. The first byte is the input length, the rest of the
+// input follows and then one byte per call; every call is checked against the plain buffer.
+use libfuzzer_sys::fuzz_target;
+use ruskell::parsec::{State, VecState};
+use std::iter::FromIterator;
+
+fuzz_target!(|data: &[u8]| {
+    if data.is_empty() {
+        return;
+    }
+    let len = (data[0] as usize).min(data.len() - 1);
+    let (input, script) = data[1..].split_at(len);
+    let mut state = VecState::from_iter(input.iter().cloned());
+    let mut trans = Vec::new();
+    for op in script {
+        let pos = state.pos();
+        assert!(pos <= input.len());
+        match op % 6 {
+            0 => match state.next() {
+                Some(x) => {
+                    assert_eq!(x, input[pos]);
+                    assert_eq!(state.pos(), pos + 1);
+                }
+                None => {
+                    assert_eq!(pos, input.len());
+                    assert_eq!(state.pos(), pos);
+                }
+            },
+            1 => {
+                // next_by consumes the element only when the predicate holds.
+                let re = state.next_by(&|x: &u8| x % 2 == 0);
+                if pos < input.len() {
+                    assert_eq!(re.is_ok(), input[pos] % 2 == 0);
+                    assert_eq!(state.pos(), if re.is_ok() { pos + 1 } else { pos });
+                } else {
+                    assert!(re.is_err());
+                    assert_eq!(state.pos(), pos);
+                }
+            }
+            2 => {
+                let tran = state.begin();
+                assert_eq!(tran, pos);
+                trans.push(tran);
+            }
+            3 => {
+                if let Some(tran) = trans.pop() {
+                    state.commit(tran);
+                    assert_eq!(state.pos(), pos);
+                }
+            }
+            4 => {
+                if let Some(tran) = trans.pop() {
+                    state.rollback(tran);
+                    assert_eq!(state.pos(), tran);
+                }
+            }
+            _ => {
+                let to = (op / 6) as usize;
+                let ok = state.seek_to(to);
+                assert_eq!(ok, to <= input.len());
+                assert_eq!(state.pos(), if ok { to } else { pos });
+            }
+        }
+        assert_eq!(state.rest(), &input[state.pos()..]);
+    }
+});
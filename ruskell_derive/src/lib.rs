@@ -0,0 +1,190 @@
+// #[derive(Parse)] writes a ruskell::parsec::derive::Parse impl for a struct or enum.
+//
+// Container and variant attributes:
+//   #[parse(prefix = "(", sep = ",", suffix = ")")]  literals around and between the fields
+//   #[parse(literal = "red")]                         same as prefix, reads better on unit variants
+// Field attributes:
+//   #[parse(before = "x=", after = ";")]             literals around one field
+//   #[parse(sep = ",")]                               a Vec field separated by a literal
+//
+// Enum variants are alternatives tried in order, a unit variant without literal matches its name.
+//
+// peg! compiles a PEG grammar into plain functions at build time, see peg.rs.
+extern crate proc_macro;
+
+mod peg;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as Tokens;
+use quote::{format_ident, quote, quote_spanned};
+use syn::{parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Fields, LitStr};
+
+#[derive(Default)]
+struct Literals {
+    prefix: Option<String>,
+    sep: Option<String>,
+    suffix: Option<String>,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+fn literals(attrs: &[Attribute]) -> syn::Result<Literals> {
+    let mut re = Literals::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("parse")) {
+        attr.parse_nested_meta(|meta| {
+            let value = meta.value()?.parse::<LitStr>()?.value();
+            let slot = if meta.path.is_ident("prefix") || meta.path.is_ident("literal") {
+                &mut re.prefix
+            } else if meta.path.is_ident("sep") {
+                &mut re.sep
+            } else if meta.path.is_ident("suffix") {
+                &mut re.suffix
+            } else if meta.path.is_ident("before") {
+                &mut re.before
+            } else if meta.path.is_ident("after") {
+                &mut re.after
+            } else {
+                return Err(meta.error("expect prefix, literal, sep, suffix, before or after"));
+            };
+            *slot = Some(value);
+            Ok(())
+        })?;
+    }
+    Ok(re)
+}
+
+fn literal(text: &Option<String>) -> Tokens {
+    match *text {
+        Some(ref text) => quote! { ::ruskell::parsec::derive::literal(#text).parse(state)?; },
+        None => quote! {},
+    }
+}
+
+// An expression of type Parser<char, Self, usize, usize> reading the fields in order and
+// building them with ctor, e.g. Self or Self::Variant.
+fn sequence(ctor: Tokens, fields: &Fields, outer: &Literals) -> syn::Result<Tokens> {
+    let mut lets = Vec::new();
+    let mut steps = Vec::new();
+    let mut names = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let attrs = literals(&field.attrs)?;
+        if attrs.prefix.is_some() || attrs.suffix.is_some() {
+            return Err(syn::Error::new_spanned(field, "use before and after on fields"));
+        }
+        let ty = &field.ty;
+        let p = format_ident!("__p{}", i);
+        let v = format_ident!("__v{}", i);
+        lets.push(match attrs.sep {
+            Some(ref sep) => quote! { let #p = <#ty as ::ruskell::parsec::derive::Repeat>::separated(#sep); },
+            None => quote! { let #p = ::ruskell::parsec::derive::field::<#ty>(); },
+        });
+        if i > 0 {
+            steps.push(literal(&outer.sep));
+        }
+        let before = literal(&attrs.before);
+        let after = literal(&attrs.after);
+        steps.push(quote! {
+            #before
+            let #v = #p.parse(state)?;
+            #after
+        });
+        names.push(v);
+    }
+    let build = match *fields {
+        Fields::Named(ref named) => {
+            let keys = named.named.iter().map(|f| f.ident.as_ref().unwrap());
+            quote! { #ctor { #(#keys: #names),* } }
+        }
+        Fields::Unnamed(_) => quote! { #ctor(#(#names),*) },
+        Fields::Unit => quote! { #ctor },
+    };
+    let prefix = literal(&outer.prefix);
+    let suffix = literal(&outer.suffix);
+    Ok(quote! {{
+        #(#lets)*
+        let p: ::ruskell::parsec::Parser<char, Self, usize, usize> = ::std::sync::Arc::new(::std::boxed::Box::new(
+            move |state: &mut dyn ::ruskell::parsec::State<char, Index=usize, Tran=usize>|
+                    -> ::ruskell::parsec::Status<Self, usize> {
+                #prefix
+                #(#steps)*
+                #suffix
+                Ok(#build)
+            }));
+        p
+    }})
+}
+
+fn expand(input: DeriveInput) -> syn::Result<Tokens> {
+    let outer = literals(&input.attrs)?;
+    let body = match input.data {
+        Data::Struct(ref data) => sequence(quote! { Self }, &data.fields, &outer)?,
+        Data::Enum(ref data) => {
+            if outer.sep.is_some() {
+                return Err(syn::Error::new_spanned(&input.ident, "put sep on the variants of an enum"));
+            }
+            let mut alternatives = Vec::new();
+            for variant in &data.variants {
+                let mut attrs = literals(&variant.attrs)?;
+                let name = &variant.ident;
+                if attrs.prefix.is_none() && variant.fields.is_empty() {
+                    attrs.prefix = Some(name.to_string());
+                }
+                alternatives.push(sequence(quote! { Self::#name }, &variant.fields, &attrs)?);
+            }
+            let prefix = literal(&outer.prefix);
+            let suffix = literal(&outer.suffix);
+            quote! {{
+                let choice = ::ruskell::parsec::derive::choice(vec![#(#alternatives),*]);
+                let p: ::ruskell::parsec::Parser<char, Self, usize, usize> = ::std::sync::Arc::new(::std::boxed::Box::new(
+                    move |state: &mut dyn ::ruskell::parsec::State<char, Index=usize, Tran=usize>|
+                            -> ::ruskell::parsec::Status<Self, usize> {
+                        #prefix
+                        let re = choice.parse(state)?;
+                        #suffix
+                        Ok(re)
+                    }));
+                p
+            }}
+        }
+        Data::Union(_) => return Err(syn::Error::new_spanned(&input.ident, "unions can not derive Parse")),
+    };
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::ruskell::parsec::derive::Parse));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::ruskell::parsec::derive::Parse for #name #ty_generics #where_clause {
+            #[allow(unused_imports)]
+            fn parser() -> ::ruskell::parsec::Parser<char, Self, usize, usize> {
+                use ::ruskell::parsec::Parsec;
+                #body
+            }
+        }
+    })
+}
+
+// syn's to_compile_error calls ::core::compile_error!, which a 2015 edition crate cannot name.
+fn compile_error(err: syn::Error) -> TokenStream {
+    let message = err.to_string();
+    quote_spanned!(err.span()=> compile_error!(#message);).into()
+}
+
+#[proc_macro_derive(Parse, attributes(parse))]
+pub fn derive_parse(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => compile_error(err),
+    }
+}
+
+#[proc_macro]
+pub fn peg(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as peg::Input);
+    match peg::expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => compile_error(err),
+    }
+}
@@ -0,0 +1,587 @@
+// peg! { pub mod name r#" grammar "# } compiles the grammar syntax of ruskell::parsec::peg into
+// a module with one plain function per rule,
+//   pub fn rule(state: &mut State<char, Index=usize, Tran=usize>) -> Status<Tree, usize>
+// plus parse(text) for the first rule. The rule functions live in name::rules and are also
+// reachable as name::rule, except a rule called parse, which only rules::parse reaches. No
+// Parser is built, so nothing is dispatched through Arc<Box<Fn>>. A name the grammar does not
+// define calls super::name(), a hand-written parser whose consumed text joins the tree; in the
+// other direction abc!(name::rule) is a Parser. A grammar with a left recursive rule or a rule
+// named like a Rust keyword does not compile.
+use proc_macro2::TokenStream as Tokens;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, LitStr, Token, Visibility};
+
+pub struct Input {
+    vis: Visibility,
+    name: Ident,
+    grammar: LitStr,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Input> {
+        let vis = input.parse()?;
+        input.parse::<Token![mod]>()?;
+        let name = input.parse()?;
+        let grammar = input.parse()?;
+        Ok(Input { vis, name, grammar })
+    }
+}
+
+enum Expr {
+    Literal(String),
+    // Ranges of the class and whether it is negated.
+    Class(Vec<(char, char)>, bool),
+    Any,
+    Rule(String),
+    Seq(Vec<Expr>),
+    Choice(Vec<Expr>),
+    Star(Box<Expr>),
+    Plus(Box<Expr>),
+    Optional(Box<Expr>),
+    And(Box<Expr>),
+    Not(Box<Expr>),
+}
+
+struct Reader {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+type Read<T> = Result<T, String>;
+
+impl Reader {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn starts_with(&self, text: &str) -> bool {
+        text.chars().enumerate().all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn fail<T>(&self, message: &str) -> Read<T> {
+        let line = self.chars[..self.pos].iter().filter(|c| **c == '\n').count() + 1;
+        let column = self.pos - self.chars[..self.pos].iter().rposition(|c| *c == '\n').map_or(0, |at| at + 1) + 1;
+        Err(format!("{} at line {}, column {} of the grammar", message, line, column))
+    }
+
+    fn space(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '#' {
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.pos += 1;
+                }
+            } else if " \t\r\n".contains(c) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn token(&mut self, text: &str) -> bool {
+        if self.starts_with(text) {
+            self.pos += text.chars().count();
+            self.space();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn name(&mut self) -> Option<String> {
+        match self.peek() {
+            Some(c) if c.is_alphabetic() || c == '_' => {}
+            _ => return None,
+        }
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.pos += 1;
+        }
+        let re = self.chars[start..self.pos].iter().collect();
+        self.space();
+        Some(re)
+    }
+
+    fn escaped(&mut self) -> Read<char> {
+        match self.peek() {
+            Some('\\') => {
+                self.pos += 1;
+                let c = match self.peek() {
+                    Some(c) => c,
+                    None => return self.fail("unterminated escape"),
+                };
+                self.pos += 1;
+                Ok(match c {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    c => c,
+                })
+            }
+            Some(c) => {
+                self.pos += 1;
+                Ok(c)
+            }
+            None => self.fail("unexpected end of grammar"),
+        }
+    }
+
+    fn definitions(&mut self) -> Read<Vec<(String, Expr)>> {
+        let mut re = Vec::new();
+        self.space();
+        while self.peek().is_some() {
+            let name = match self.name() {
+                Some(name) => name,
+                None => return self.fail("expect a rule name"),
+            };
+            if !self.token("<-") {
+                return self.fail("expect <-");
+            }
+            if re.iter().any(|(n, _)| *n == name) {
+                return self.fail(&format!("rule {} defined twice", name));
+            }
+            let expr = self.choice()?;
+            re.push((name, expr));
+        }
+        if re.is_empty() {
+            return self.fail("expect at least one rule");
+        }
+        Ok(re)
+    }
+
+    fn choice(&mut self) -> Read<Expr> {
+        let mut items = vec![self.sequence()?];
+        while self.token("/") {
+            items.push(self.sequence()?);
+        }
+        Ok(if items.len() == 1 { items.pop().unwrap() } else { Expr::Choice(items) })
+    }
+
+    fn sequence(&mut self) -> Read<Expr> {
+        let mut items = Vec::new();
+        while let Some(item) = self.prefixed()? {
+            items.push(item);
+        }
+        match items.len() {
+            0 => self.fail("expect an expression"),
+            1 => Ok(items.pop().unwrap()),
+            _ => Ok(Expr::Seq(items)),
+        }
+    }
+
+    fn prefixed(&mut self) -> Read<Option<Expr>> {
+        if self.token("&") {
+            return self.required().map(|x| Some(Expr::And(Box::new(x))));
+        }
+        if self.token("!") {
+            return self.required().map(|x| Some(Expr::Not(Box::new(x))));
+        }
+        let x = match self.primary()? {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+        Ok(Some(if self.token("*") {
+            Expr::Star(Box::new(x))
+        } else if self.token("+") {
+            Expr::Plus(Box::new(x))
+        } else if self.token("?") {
+            Expr::Optional(Box::new(x))
+        } else {
+            x
+        }))
+    }
+
+    fn required(&mut self) -> Read<Expr> {
+        match self.prefixed()? {
+            Some(x) => Ok(x),
+            None => self.fail("expect an expression"),
+        }
+    }
+
+    fn primary(&mut self) -> Read<Option<Expr>> {
+        let start = self.pos;
+        if let Some(name) = self.name() {
+            if self.starts_with("<-") {
+                // The start of the next definition.
+                self.pos = start;
+                return Ok(None);
+            }
+            return Ok(Some(Expr::Rule(name)));
+        }
+        match self.peek() {
+            Some('(') => {
+                self.token("(");
+                let x = self.choice()?;
+                if !self.token(")") {
+                    return self.fail("expect )");
+                }
+                Ok(Some(x))
+            }
+            Some(q) if q == '\'' || q == '"' => {
+                self.pos += 1;
+                let mut text = String::new();
+                while self.peek() != Some(q) {
+                    text.push(self.escaped()?);
+                }
+                self.token(&q.to_string());
+                Ok(Some(Expr::Literal(text)))
+            }
+            Some('[') => {
+                self.pos += 1;
+                let negated = self.peek() == Some('^');
+                if negated {
+                    self.pos += 1;
+                }
+                let mut ranges = Vec::new();
+                while self.peek() != Some(']') {
+                    let lo = self.escaped()?;
+                    let hi = if self.peek() == Some('-') && self.chars.get(self.pos + 1) != Some(&']') {
+                        self.pos += 1;
+                        self.escaped()?
+                    } else {
+                        lo
+                    };
+                    ranges.push((lo, hi));
+                }
+                self.token("]");
+                Ok(Some(Expr::Class(ranges, negated)))
+            }
+            Some('.') => {
+                self.token(".");
+                Ok(Some(Expr::Any))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+// The same analysis as Grammar::new at runtime. Rules the grammar does not define are
+// hand-written parsers, taken to consume input and to call no rule of the grammar.
+fn nullable(expr: &Expr, names: &[String], rules: &[bool]) -> bool {
+    match *expr {
+        Expr::Literal(ref text) => text.is_empty(),
+        Expr::Class(..) | Expr::Any => false,
+        Expr::Rule(ref name) => names.iter().position(|n| n == name).is_some_and(|at| rules[at]),
+        Expr::Seq(ref items) => items.iter().all(|x| nullable(x, names, rules)),
+        Expr::Choice(ref items) => items.iter().any(|x| nullable(x, names, rules)),
+        Expr::Plus(ref x) => nullable(x, names, rules),
+        Expr::Star(_) | Expr::Optional(_) | Expr::And(_) | Expr::Not(_) => true,
+    }
+}
+
+// Rules the expression may call before it has consumed anything.
+fn leftmost(expr: &Expr, names: &[String], rules: &[bool], re: &mut Vec<usize>) {
+    match *expr {
+        Expr::Rule(ref name) => re.extend(names.iter().position(|n| n == name)),
+        Expr::Seq(ref items) => {
+            for x in items {
+                leftmost(x, names, rules, re);
+                if !nullable(x, names, rules) {
+                    break;
+                }
+            }
+        }
+        Expr::Choice(ref items) => {
+            for x in items {
+                leftmost(x, names, rules, re);
+            }
+        }
+        Expr::Star(ref x) | Expr::Plus(ref x) | Expr::Optional(ref x) | Expr::And(ref x) | Expr::Not(ref x) => {
+            leftmost(x, names, rules, re)
+        }
+        _ => {}
+    }
+}
+
+// The first rule that can call itself again without consuming anything.
+fn left_recursive(exprs: &[&Expr], names: &[String]) -> Option<usize> {
+    let mut rules = vec![false; exprs.len()];
+    loop {
+        let next = exprs.iter().map(|x| nullable(x, names, &rules)).collect::<Vec<bool>>();
+        if next == rules {
+            break;
+        }
+        rules = next;
+    }
+    let calls = exprs
+        .iter()
+        .map(|x| {
+            let mut re = Vec::new();
+            leftmost(x, names, &rules, &mut re);
+            re
+        })
+        .collect::<Vec<_>>();
+    (0..exprs.len()).find(|&start| {
+        let mut seen = vec![false; exprs.len()];
+        let mut todo = calls[start].clone();
+        while let Some(at) = todo.pop() {
+            if at == start {
+                return true;
+            }
+            if !seen[at] {
+                seen[at] = true;
+                todo.extend(calls[at].iter().cloned());
+            }
+        }
+        false
+    })
+}
+
+fn failure(message: &str) -> Tokens {
+    quote! { Result::Err(__state.err(String::from(#message))) }
+}
+
+// An expression of type Status<(), usize> that appends what it matched to __text and
+// __children. Alternatives and repetitions take their own transaction and cut both back.
+// Rules are called by path and Ok, Err and Some through their types, so a rule may be named
+// like a local or a prelude function.
+fn generate(expr: &Expr, rules: &[String]) -> Tokens {
+    let undo = quote! {
+        __state.rollback(__tran);
+        __text.truncate(__mark.0);
+        __children.truncate(__mark.1);
+    };
+    match *expr {
+        Expr::Literal(ref literal) => {
+            let fail = failure(&format!("expect {:?}", literal));
+            quote! {{
+                let __tran = __state.begin();
+                if #literal.chars().all(|__c| __state.next() == Option::Some(__c)) {
+                    __state.commit(__tran);
+                    __text.push_str(#literal);
+                    Result::Ok(())
+                } else {
+                    __state.rollback(__tran);
+                    #fail
+                }
+            }}
+        }
+        Expr::Class(ref ranges, negated) => {
+            let tests = ranges.iter().map(|&(lo, hi)| {
+                if lo == hi { quote! { __c == #lo } } else { quote! { (#lo <= __c && __c <= #hi) } }
+            });
+            let describe = ranges.iter().map(|&(lo, hi)| {
+                if lo == hi { lo.to_string() } else { format!("{}-{}", lo, hi) }
+            }).collect::<String>();
+            let fail = failure(&format!("expect [{}{}]", if negated { "^" } else { "" }, describe));
+            quote! {{
+                let __tran = __state.begin();
+                match __state.next() {
+                    Option::Some(__c) if (false #(|| #tests)*) != #negated => {
+                        __state.commit(__tran);
+                        __text.push(__c);
+                        Result::Ok(())
+                    }
+                    _ => {
+                        __state.rollback(__tran);
+                        #fail
+                    }
+                }
+            }}
+        }
+        Expr::Any => {
+            let fail = failure("eof");
+            quote! {
+                match __state.next() {
+                    Option::Some(__c) => {
+                        __text.push(__c);
+                        Result::Ok(())
+                    }
+                    Option::None => #fail,
+                }
+            }
+        }
+        Expr::Rule(ref name) if rules.contains(name) => {
+            let name = format_ident!("{}", name);
+            quote! {
+                self::#name(__state).map(|__tree| {
+                    __text.push_str(&__tree.text);
+                    __children.push(__tree);
+                })
+            }
+        }
+        Expr::Rule(ref name) => {
+            let name = format_ident!("{}", name);
+            quote! {{
+                let __start = __state.pos();
+                match ::ruskell::parsec::Parsec::parse(&super::super::#name(), __state) {
+                    Result::Ok(_) => {
+                        let __end = __state.pos();
+                        __state.seek_to(__start);
+                        while __state.pos() < __end {
+                            match __state.next() {
+                                Option::Some(__c) => __text.push(__c),
+                                Option::None => break,
+                            }
+                        }
+                        Result::Ok(())
+                    }
+                    Result::Err(__err) => Result::Err(__err),
+                }
+            }}
+        }
+        Expr::Seq(ref items) => {
+            let items = items.iter().map(|x| generate(x, rules));
+            quote! {
+                (|| -> Status<(), usize> {
+                    #( (#items)?; )*
+                    Result::Ok(())
+                })()
+            }
+        }
+        Expr::Choice(ref items) => {
+            let mut items = items.iter().rev().map(|x| generate(x, rules));
+            let last = items.next().unwrap();
+            items.fold(last, |rest, item| quote! {{
+                let __mark = (__text.len(), __children.len());
+                let __tran = __state.begin();
+                match #item {
+                    Result::Ok(()) => {
+                        __state.commit(__tran);
+                        Result::Ok(())
+                    }
+                    Result::Err(_) => {
+                        #undo
+                        #rest
+                    }
+                }
+            }})
+        }
+        Expr::Star(ref x) => {
+            let x = generate(x, rules);
+            quote! {{
+                loop {
+                    let __mark = (__text.len(), __children.len());
+                    let __tran = __state.begin();
+                    let __before = __state.pos();
+                    let __re: Status<(), usize> = #x;
+                    match __re {
+                        Result::Ok(()) if __state.pos() != __before => __state.commit(__tran),
+                        Result::Ok(()) => {
+                            __state.commit(__tran);
+                            break;
+                        }
+                        Result::Err(_) => {
+                            #undo
+                            break;
+                        }
+                    }
+                }
+                Result::Ok(())
+            }}
+        }
+        Expr::Plus(ref x) => {
+            let first = generate(x, rules);
+            let rest = generate(&Expr::Star(Box::new(clone(x))), rules);
+            quote! { (#first).and_then(|_| #rest) }
+        }
+        Expr::Optional(ref x) => {
+            let x = generate(x, rules);
+            quote! {{
+                let __mark = (__text.len(), __children.len());
+                let __tran = __state.begin();
+                let __re: Status<(), usize> = #x;
+                match __re {
+                    Result::Ok(()) => __state.commit(__tran),
+                    Result::Err(_) => { #undo }
+                }
+                Result::Ok(())
+            }}
+        }
+        Expr::And(ref x) | Expr::Not(ref x) => {
+            let x = generate(x, rules);
+            let fail = failure("unexpected input");
+            let result = match *expr {
+                Expr::And(_) => quote! { __re },
+                _ => quote! {
+                    match __re {
+                        Result::Ok(()) => #fail,
+                        Result::Err(_) => Result::Ok(()),
+                    }
+                },
+            };
+            quote! {{
+                let __mark = (__text.len(), __children.len());
+                let __tran = __state.begin();
+                let __re: Status<(), usize> = #x;
+                #undo
+                #result
+            }}
+        }
+    }
+}
+
+fn clone(expr: &Expr) -> Expr {
+    let boxed = |x: &Expr| Box::new(clone(x));
+    match *expr {
+        Expr::Literal(ref x) => Expr::Literal(x.clone()),
+        Expr::Class(ref x, negated) => Expr::Class(x.clone(), negated),
+        Expr::Any => Expr::Any,
+        Expr::Rule(ref x) => Expr::Rule(x.clone()),
+        Expr::Seq(ref x) => Expr::Seq(x.iter().map(clone).collect()),
+        Expr::Choice(ref x) => Expr::Choice(x.iter().map(clone).collect()),
+        Expr::Star(ref x) => Expr::Star(boxed(x)),
+        Expr::Plus(ref x) => Expr::Plus(boxed(x)),
+        Expr::Optional(ref x) => Expr::Optional(boxed(x)),
+        Expr::And(ref x) => Expr::And(boxed(x)),
+        Expr::Not(ref x) => Expr::Not(boxed(x)),
+    }
+}
+
+pub fn expand(input: Input) -> syn::Result<Tokens> {
+    let mut reader = Reader { chars: input.grammar.value().chars().collect(), pos: 0 };
+    let definitions = reader.definitions().map_err(|message| syn::Error::new(input.grammar.span(), message))?;
+    let names = definitions.iter().map(|(name, _)| name.clone()).collect::<Vec<String>>();
+    // Grammar::new takes any name, but a rule here is a function.
+    if let Some(name) = names.iter().find(|name| syn::parse_str::<Ident>(name).is_err()) {
+        return Err(syn::Error::new(input.grammar.span(), format!("rule {} is a Rust keyword", name)));
+    }
+    let exprs = definitions.iter().map(|(_, expr)| expr).collect::<Vec<&Expr>>();
+    if let Some(at) = left_recursive(&exprs, &names) {
+        return Err(syn::Error::new(input.grammar.span(), format!("rule {} is left recursive", names[at])));
+    }
+    let functions = definitions.iter().map(|(name, expr)| {
+        let ident = format_ident!("{}", name);
+        let body = generate(expr, &names);
+        quote! {
+            pub fn #ident(__state: &mut State<char, Index=usize, Tran=usize>) -> Status<Tree, usize> {
+                let __start = __state.pos();
+                let mut __text = String::new();
+                let mut __children = Vec::new();
+                {
+                    let __text = &mut __text;
+                    let __children = &mut __children;
+                    (#body)?;
+                }
+                Result::Ok(Tree { rule: String::from(#name), text: __text, span: Span::new(__start, __state.pos()), children: __children })
+            }
+        }
+    });
+    let start = format_ident!("{}", names[0]);
+    let vis = &input.vis;
+    let module = &input.name;
+    // The rules come in through a glob, which the parse entry point shadows, so a rule named
+    // parse is still there as rules::parse.
+    Ok(quote! {
+        #vis mod #module {
+            #![allow(unused_parens, unused_mut, unused_variables, unused_imports, unused_braces, dead_code, non_snake_case)]
+            pub use self::rules::*;
+
+            pub mod rules {
+                #![allow(unused_parens, unused_mut, unused_variables, unused_imports, unused_braces, dead_code, non_snake_case)]
+                use ::ruskell::parsec::{State, Status};
+                use ::ruskell::parsec::peg::Tree;
+                use ::ruskell::parsec::span::Span;
+
+                #(#functions)*
+            }
+
+            pub fn parse(__input: &str) -> ::ruskell::parsec::Status<::ruskell::parsec::peg::Tree, usize> {
+                let __p: ::ruskell::parsec::Parser<char, ::ruskell::parsec::peg::Tree, usize, usize> =
+                    ::std::sync::Arc::new(::std::boxed::Box::new(self::rules::#start));
+                ::ruskell::parsec::parse_complete(__p, __input).into_result()
+            }
+        }
+    })
+}
@@ -0,0 +1,210 @@
+use parsec::{State, Status, Parser, Parsec, Monad, ParsecError, Error};
+use parsec::atom::{eq, none_of, one, tag, eof, pack};
+use parsec::charset::CharSet;
+use parsec::combinator::{either, try, many, sep_by, between};
+use parsec::stream::StreamState;
+use parsec::text::newline;
+use std::cell::RefCell;
+use std::error;
+use std::fmt::{Formatter, Display};
+use std::fmt;
+use std::rc::Rc;
+use std::sync::Arc;
+
+// RFC 4180 records. With escape None a quote inside a quoted field is written twice,
+// otherwise the escape char makes the next char literal.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub delimiter: char,
+    pub quote: char,
+    pub escape: Option<char>,
+    pub header: bool,
+}
+
+impl Config {
+    pub fn csv()->Config {
+        Config{delimiter:',', quote:'"', escape:None, header:false}
+    }
+
+    pub fn tsv()->Config {
+        Config{delimiter:'\t', quote:'"', escape:None, header:false}
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for CsvError {
+    fn fmt(&self, formatter:&mut Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{} at line {}, column {}", self.message, self.line, self.column)
+    }
+}
+
+impl error::Error for CsvError {
+    fn description(&self)->&str {
+        self.message.as_str()
+    }
+}
+
+pub fn field(config:&Config)->Parser<char, String, usize, usize> {
+    let quote = config.quote;
+    let inner = match config.escape {
+        None => either(try(tag(&[quote, quote])).then(pack(quote)), none_of(vec![quote])),
+        Some(escape) => either(try(eq(escape)).then(one()), none_of(vec![quote, escape])),
+    };
+    let quoted = between(try(eq(quote)), eq(quote), many(inner));
+    let plain = many(none_of(CharSet::chars("\r\n").with_chars(&format!("{}{}", config.delimiter, quote))));
+    let chars = either(quoted, plain);
+    abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<String, usize>{
+        chars.parse(state).map(|data| data.into_iter().collect())
+    })
+}
+
+// One record and its line ending. Unquoted fields take anything up to a delimiter, quote or
+// line break, so a record that does not end cleanly always has a malformed quoted field.
+pub fn record(config:&Config)->Parser<char, Vec<String>, usize, usize> {
+    let fields = sep_by(field(config), eq(config.delimiter));
+    let delimiter = config.delimiter;
+    let quote = config.quote;
+    let end = either(newline().then(pack(())), eof());
+    abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<Vec<String>, usize>{
+        let re = try!(fields.parse(state));
+        let pos = state.pos();
+        let tran = state.begin();
+        let stop = state.next();
+        state.rollback(tran);
+        if end.parse(state).is_ok() {
+            return Ok(re);
+        }
+        let (pos, description) = match stop {
+            Some(c) if c == delimiter => (pos + 1, "unterminated quoted field"),
+            _ if re.is_empty() => (pos, "unterminated quoted field"),
+            Some(c) if c == quote => (pos, "unexpected quote in field"),
+            _ => (pos, "expect delimiter or line end after closing quote"),
+        };
+        Err(ParsecError::new(pos, String::from(description)))
+    })
+}
+
+// Only the newlines the current record may still report against are kept; the ones before
+// it are folded into the count of finished lines and the offset where the next line starts.
+struct LineIndex {
+    line: usize,
+    start: usize,
+    newlines: Vec<usize>,
+}
+
+impl LineIndex {
+    fn forget(&mut self, pos:usize) {
+        let gone = self.newlines.iter().take_while(|&&n| n < pos).count();
+        if gone > 0 {
+            self.line += gone;
+            self.start = self.newlines[gone - 1] + 1;
+            self.newlines.drain(..gone);
+        }
+    }
+
+    fn locate(&self, pos:usize)->(usize, usize) {
+        let before = self.newlines.binary_search(&pos).unwrap_or_else(|at| at);
+        if before == 0 {
+            (self.line + 1, pos - self.start + 1)
+        } else {
+            (self.line + before + 1, pos - self.newlines[before - 1])
+        }
+    }
+}
+
+struct Lines<I> {
+    source: I,
+    pos: usize,
+    index: Rc<RefCell<LineIndex>>,
+}
+
+impl<I:Iterator<Item=char>> Iterator for Lines<I> {
+    type Item = char;
+    fn next(&mut self)->Option<char> {
+        let c = self.source.next();
+        if c == Some('\n') {
+            self.index.borrow_mut().newlines.push(self.pos);
+        }
+        if c.is_some() {
+            self.pos += 1;
+        }
+        c
+    }
+}
+
+// Streams records from any char iterator; stops after the first error.
+pub struct Reader<I:Iterator<Item=char>> {
+    state: StreamState<Lines<I>>,
+    index: Rc<RefCell<LineIndex>>,
+    record: Parser<char, Vec<String>, usize, usize>,
+    header: Option<Vec<String>>,
+    done: bool,
+}
+
+impl<I:Iterator<Item=char>> Reader<I> {
+    pub fn new(source:I, config:Config)->Result<Reader<I>, CsvError> {
+        let index = Rc::new(RefCell::new(LineIndex{line:0, start:0, newlines:Vec::new()}));
+        let lines = Lines{source:source, pos:0, index:index.clone()};
+        let mut reader = Reader{
+            state: StreamState::new(lines),
+            index: index,
+            record: record(&config),
+            header: None,
+            done: false,
+        };
+        if config.header {
+            reader.header = match reader.next() {
+                Some(Ok(header)) => Some(header),
+                Some(Err(err)) => return Err(err),
+                None => Some(Vec::new()),
+            };
+        }
+        Ok(reader)
+    }
+
+    pub fn header(&self)->Option<&Vec<String>> {
+        self.header.as_ref()
+    }
+
+    // Chars and line ends held in memory right now.
+    pub fn buffered(&self)->usize {
+        self.state.buffered() + self.index.borrow().newlines.len()
+    }
+}
+
+impl<I:Iterator<Item=char>> Iterator for Reader<I> {
+    type Item = Result<Vec<String>, CsvError>;
+    fn next(&mut self)->Option<Result<Vec<String>, CsvError>> {
+        if self.done {
+            return None;
+        }
+        let tran = self.state.begin();
+        let end = self.state.next().is_none();
+        self.state.rollback(tran);
+        if end {
+            self.done = true;
+            return None;
+        }
+        match self.record.parse(&mut self.state) {
+            Ok(fields) => {
+                self.index.borrow_mut().forget(self.state.pos());
+                Some(Ok(fields))
+            }
+            Err(err) => {
+                self.done = true;
+                let (line, column) = self.index.borrow().locate(err.pos());
+                Some(Err(CsvError{line:line, column:column, message:String::from(error::Error::description(&err))}))
+            }
+        }
+    }
+}
+
+pub fn read(text:&str, config:Config)->Result<Vec<Vec<String>>, CsvError> {
+    try!(Reader::new(text.chars(), config)).collect()
+}
@@ -0,0 +1,168 @@
+use parsec::{State, Status, Parser, Parsec, Monad, ParsecError, parse as run};
+use parsec::atom::{eq, one_of, none_of, string, satisfy, take_till, eof, pack};
+use parsec::charset::CharSet;
+use parsec::combinator::{either, try, many1, many_till, sep_by, sep_by1, between, skip, fix, Or};
+use parsec::text::{newline, integer, float};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+// INI files with a TOML flavoured value syntax. Sections may be dotted to nest tables, values
+// are typed when they read as a bool, number, quoted string or array, else kept as bare text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Value>),
+    Table(BTreeMap<String, Value>),
+}
+
+#[derive(Debug, Clone)]
+enum Line {
+    Section(Vec<String>),
+    Pair(String, Value),
+    Blank,
+}
+
+pub fn parse(text:&str)->Status<BTreeMap<String, Value>, usize> {
+    let lines = try!(run(many_till(line(), eof()), text).into_result());
+    let mut root = BTreeMap::new();
+    let mut path = Vec::new();
+    for (pos, line) in lines {
+        match line {
+            Line::Section(names) => {
+                try!(table(&mut root, &names, pos));
+                path = names;
+            }
+            Line::Pair(key, value) => {
+                let table = try!(table(&mut root, &path, pos));
+                if table.contains_key(&key) {
+                    return Err(ParsecError::new(pos, format!("duplicate key {}", key)));
+                }
+                table.insert(key, value);
+            }
+            Line::Blank => {}
+        }
+    }
+    Ok(root)
+}
+
+fn table<'a>(root:&'a mut BTreeMap<String, Value>, path:&[String], pos:usize)
+        ->Status<&'a mut BTreeMap<String, Value>, usize> {
+    let mut current = root;
+    for name in path {
+        let entry = current.entry(name.clone()).or_insert_with(|| Value::Table(BTreeMap::new()));
+        current = match *entry {
+            Value::Table(ref mut table) => table,
+            _ => return Err(ParsecError::new(pos, format!("{} is not a section", name))),
+        };
+    }
+    Ok(current)
+}
+
+fn line()->Parser<char, (usize, Line), usize, usize> {
+    let section = map(between(try(eq('[')).over(blank()), eq(']').over(line_end()),
+        sep_by1(name().over(blank()), eq('.').over(blank()))), Line::Section);
+    let pair = name().over(blank()).over(one_of("=:")).over(blank()).bind(abc!(|key:String, state: &mut State<char, Index=usize, Tran=usize>|->Status<Line, usize>{
+        value_line().parse(state).map(|value| Line::Pair(key.clone(), value))
+    }));
+    let empty = line_end().then(pack(Line::Blank));
+    let line = either(section, pair).or(empty);
+    let blank = blank();
+    abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<(usize, Line), usize>{
+        try!(blank.parse(state));
+        let pos = state.pos();
+        line.parse(state).map(|line| (pos, line))
+    })
+}
+
+// Quoted strings and arrays commit on their opening char, scalars fall back to bare text when
+// the rest of the line does not fit, as in `version = 1.2.3`.
+fn value_line()->Parser<char, Value, usize, usize> {
+    let structured = either(quoted(), array()).over(line_end());
+    let typed = try(scalar().over(line_end()));
+    let bare = satisfy(|c:&char| !"\"'[;#\r\n".contains(*c)).bind(abc!(|first:char, state: &mut State<char, Index=usize, Tran=usize>|->Status<Value, usize>{
+        let rest = try!(take_till(|c:&char| ";#\r\n".contains(*c)).parse(state));
+        let mut text = String::new();
+        text.push(first);
+        text.extend(rest);
+        Ok(Value::String(String::from(text.trim_end())))
+    })).over(line_end());
+    either(structured, typed).or(bare)
+}
+
+pub fn value()->Parser<char, Value, usize, usize> {
+    either(quoted(), array()).or(scalar())
+}
+
+fn scalar()->Parser<char, Value, usize, usize> {
+    let yes = try(string("true")).then(pack(Value::Bool(true)));
+    let no = try(string("false")).then(pack(Value::Bool(false)));
+    let number = try(map(float(), |x:String| Value::Float(x.parse::<f64>().unwrap())))
+        .or(integer().bind(abc!(|x:String, state: &mut State<char, Index=usize, Tran=usize>|->Status<Value, usize>{
+            x.parse::<i64>().map(Value::Integer).map_err(|_| state.err(format!("integer {} out of range", x)))
+        })));
+    either(yes, no).or(number)
+}
+
+fn quoted()->Parser<char, Value, usize, usize> {
+    let escape = eq('\\').then(one_of("\"\\nrt")).bind(abc!(|c:char, _: &mut State<char, Index=usize, Tran=usize>|->Status<char, usize>{
+        Ok(match c {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            c => c,
+        })
+    }));
+    let basic = try(eq('"')).then(many_till(either(try(none_of(CharSet::chars("\\\r\n"))), escape), eq('"')));
+    let literal = try(eq('\'')).then(many_till(none_of(CharSet::chars("\r\n")), eq('\'')));
+    map(either(basic, literal), |chars:Vec<char>| Value::String(chars.into_iter().collect()))
+}
+
+// Arrays may span lines and hold comments between items.
+fn array()->Parser<char, Value, usize, usize> {
+    fix(|array| {
+        let item = either(quoted(), array).or(scalar());
+        let items = sep_by(item.over(gap()), eq(',').over(gap()));
+        map(between(try(eq('[')).over(gap()), eq(']'), items), Value::Array)
+    })
+}
+
+fn name()->Parser<char, String, usize, usize> {
+    map(many1(satisfy(|c:&char| c.is_alphanumeric() || *c == '_' || *c == '-')), |chars:Vec<char>| {
+        chars.into_iter().collect()
+    })
+}
+
+fn blank()->Parser<char, Vec<char>, usize, usize> {
+    skip(one_of(" \t"))
+}
+
+fn comment()->Parser<char, Vec<char>, usize, usize> {
+    one_of(";#").then(take_till(|c:&char| *c == '\r' || *c == '\n'))
+}
+
+fn gap()->Parser<char, Vec<char>, usize, usize> {
+    skip(either(try(one_of(" \t\r\n")), comment().then(pack(' '))))
+}
+
+fn line_end()->Parser<char, (), usize, usize> {
+    blank().then(skip(comment())).then(either(newline().then(pack(())), eof()))
+}
+
+fn map<R:Clone+'static, S:Clone+'static, F:'static>(p:Parser<char, R, usize, usize>, f:F)->Parser<char, S, usize, usize>
+where F:Fn(R)->S {
+    p.bind(abc!(move |x:R, _: &mut State<char, Index=usize, Tran=usize>|->Status<S, usize>{
+        Ok(f(x))
+    }))
+}
+
+impl Value {
+    pub fn get(&self, key:&str)->Option<&Value> {
+        match *self {
+            Value::Table(ref members) => members.get(key),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use parsec::{State, Status, Parser, Parsec, Monad, parse_complete};
+use parsec::atom::{eq, one_of, none_of, string, pack};
+use parsec::charset::CharSet;
+use parsec::combinator::{either, try, many, between, skip, fix, otherwise, Or};
+use parsec::regex::regex;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+// RFC 8259 JSON, written with the parsec combinators only.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(BTreeMap<String, Value>),
+}
+
+pub fn parse(text:&str)->Status<Value, usize> {
+    parse_complete(ws().then(value()), text).into_result()
+}
+
+pub fn value()->Parser<char, Value, usize, usize> {
+    fix(|value| {
+        let null = string("null").then(pack(Value::Null));
+        let yes = string("true").then(pack(Value::Bool(true)));
+        let no = string("false").then(pack(Value::Bool(false)));
+        let number = map(number(), Value::Number);
+        let quoted = map(text(), Value::String);
+        let array = map(list('[', value.clone(), ']'), Value::Array);
+        let member = text().over(ws()).over(token(':')).bind(abc!(move |key:String, state: &mut State<char, Index=usize, Tran=usize>|->Status<(String, Value), usize>{
+            value.parse(state).map(|v| (key.clone(), v))
+        }));
+        let object = map(list('{', member, '}'), |members:Vec<(String, Value)>| {
+            Value::Object(members.into_iter().collect())
+        });
+        let any = either(null, yes).or(no).or(number).or(quoted).or(array).or(object);
+        otherwise(any, String::from("expect value")).over(ws())
+    })
+}
+
+pub fn number()->Parser<char, f64, usize, usize> {
+    map(regex(r"-?(0|[1-9]\d*)(\.\d+)?([eE][+-]?\d+)?"), |x:String| x.parse::<f64>().unwrap())
+}
+
+pub fn text()->Parser<char, String, usize, usize> {
+    let plain = map(none_of(CharSet::chars("\"\\").with_range('\u{0}'..='\u{1f}')), |c:char| {
+        let mut units = [0u16; 2];
+        c.encode_utf16(&mut units).to_vec()
+    });
+    let simple = map(one_of("\"\\/bfnrt"), |c:char| vec![match c {
+        'b' => 0x08,
+        'f' => 0x0c,
+        'n' => 0x0a,
+        'r' => 0x0d,
+        't' => 0x09,
+        c => c as u16,
+    }]);
+    let unicode = map(eq('u').then(regex("[0-9a-fA-F]{4}")), |hex:String| vec![u16::from_str_radix(&hex, 16).unwrap()]);
+    let escape = eq('\\').then(either(try(simple), unicode));
+    let units = many(either(try(plain), escape));
+    map(between(try(eq('"')), eq('"'), units), |units:Vec<Vec<u16>>| {
+        String::from_utf16_lossy(&units.concat())
+    })
+}
+
+// open, comma separated items, close. Once an item is due it has to parse, so "[1, tru]" fails
+// at tru instead of backing up to the comma.
+fn list<R:Clone+'static>(open:char, item:Parser<char, R, usize, usize>, close:char)->Parser<char, Vec<R>, usize, usize> {
+    let (open, comma, close) = (token(open), token(','), token(close));
+    abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<Vec<R>, usize>{
+        try!(open.parse(state));
+        let mut re = Vec::new();
+        if close.parse(state).is_ok() {
+            return Ok(re);
+        }
+        loop {
+            re.push(try!(item.parse(state)));
+            if close.parse(state).is_ok() {
+                return Ok(re);
+            }
+            try!(comma.parse(state));
+        }
+    })
+}
+
+fn ws()->Parser<char, Vec<char>, usize, usize> {
+    skip(one_of(" \t\n\r"))
+}
+
+fn token(c:char)->Parser<char, char, usize, usize> {
+    try(eq(c)).over(ws())
+}
+
+fn map<R:Clone+'static, S:Clone+'static, F:'static>(p:Parser<char, R, usize, usize>, f:F)->Parser<char, S, usize, usize>
+where F:Fn(R)->S {
+    p.bind(abc!(move |x:R, _: &mut State<char, Index=usize, Tran=usize>|->Status<S, usize>{
+        Ok(f(x))
+    }))
+}
+
+impl Value {
+    pub fn get(&self, key:&str)->Option<&Value> {
+        match *self {
+            Value::Object(ref members) => members.get(key),
+            _ => None,
+        }
+    }
+}
@@ -1,12 +1,56 @@
-#![feature(unboxed_closures)]
-#![feature(custom_derive)]
-#![feature(reflect_marker)]
-
 //Arc<Box<Closure>>
 #[macro_export]
 macro_rules! abc {
     ($x:expr) => (Arc::new(Box::new($x)))
 }
 
+// Haskell do notation for parsers. Each line runs on the same state, a failure returns at once:
+//   parse_do!{ x <- digit(); _ <- eq(','); let n = x as u8; y <- digit(); ret (n, y) }
+// Parsers are built when the rule runs, so later lines can use the values bound before them.
+// The last line is either `ret expr` or a parser whose result is the result of the whole block.
+#[macro_export]
+macro_rules! parse_do {
+    (@run $state:ident; ret $e:expr $(;)*) => (Ok($e));
+    (@run $state:ident; $x:ident <- $p:expr; $($rest:tt)+) => ({
+        let $x = match $crate::parsec::Parsec::parse(&$p, $state) {
+            Ok(x) => x,
+            Err(err) => return Err(err),
+        };
+        $crate::parse_do!(@run $state; $($rest)+)
+    });
+    (@run $state:ident; mut $x:ident <- $p:expr; $($rest:tt)+) => ({
+        let mut $x = match $crate::parsec::Parsec::parse(&$p, $state) {
+            Ok(x) => x,
+            Err(err) => return Err(err),
+        };
+        $crate::parse_do!(@run $state; $($rest)+)
+    });
+    (@run $state:ident; _ <- $p:expr; $($rest:tt)+) => ($crate::parse_do!(@run $state; $p; $($rest)+));
+    (@run $state:ident; let $x:pat = $e:expr; $($rest:tt)+) => ({
+        let $x = $e;
+        $crate::parse_do!(@run $state; $($rest)+)
+    });
+    (@run $state:ident; $p:expr; $($rest:tt)+) => ({
+        if let Err(err) = $crate::parsec::Parsec::parse(&$p, $state) {
+            return Err(err);
+        }
+        $crate::parse_do!(@run $state; $($rest)+)
+    });
+    (@run $state:ident; $p:expr $(;)*) => ($crate::parsec::Parsec::parse(&$p, $state));
+    ($($body:tt)+) => ({
+        let p:$crate::parsec::Parser<_, _, _, _> = ::std::sync::Arc::new(::std::boxed::Box::new(
+            move |state: &mut $crate::parsec::State<_, Index=_, Tran=_>| {
+                $crate::parse_do!(@run state; $($body)+)
+            }));
+        p
+    });
+}
+
 pub mod functional;
 pub mod parsec;
+pub mod json;
+pub mod csv;
+pub mod ini;
+pub mod sexpr;
+pub mod pretty;
+pub mod syntax;
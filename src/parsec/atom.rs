@@ -1,19 +1,19 @@
 use parsec::{State, ParsecError, Status, Parser};
+use parsec::charset::{Set, IntoSet};
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
-use std::marker::Reflect;
 
-pub fn one<T:'static, Index:Reflect+Debug+'static, Tran:'static>()->Parser<T, T, Index, Tran> {
+pub fn one<T:'static, Index:Debug+'static, Tran:'static>()->Parser<T, T, Index, Tran> {
     abc!(|state:&mut State<T, Index=Index, Tran=Tran>|->Status<T, Index>{
         state.next().ok_or(ParsecError::new(state.pos(), String::from("eof")))
     })
 }
 
-pub fn eq<T:'static, Index:Reflect+Debug+Display+'static, Tran:'static>(val:T)
+pub fn eq<T:'static, Index:Debug+Display+'static, Tran:'static>(val:T)
             -> Parser<T, T, Index, Tran> where T:Eq+Display+Debug+Clone {
     abc!(move |state:&mut State<T, Index=Index, Tran=Tran>|->Status<T, Index>{
-        let value = state.next();
         let pos = state.pos();
+        let value = state.next();
         if value.is_some() {
             let x = value.unwrap();
             if x == val {
@@ -28,11 +28,11 @@ pub fn eq<T:'static, Index:Reflect+Debug+Display+'static, Tran:'static>(val:T)
 }
 
 
-pub fn ne<T:'static, Index:Reflect+Debug+Display+'static, Tran:'static>(val:T)
+pub fn ne<T:'static, Index:Debug+Display+'static, Tran:'static>(val:T)
             -> Parser<T, T, Index, Tran> where T:Display+Eq+Debug+Clone {
     abc!(move |state:&mut State<T, Index=Index, Tran=Tran>|->Status<T, Index>{
-        let value = state.next();
         let pos = state.pos();
+        let value = state.next();
         if value.is_some() {
             let x = value.unwrap();
             if x == val {
@@ -46,65 +46,157 @@ pub fn ne<T:'static, Index:Reflect+Debug+Display+'static, Tran:'static>(val:T)
     })
 }
 
-pub fn eof<T:'static+Display, Index:Reflect+Debug+Display+'static, Tran:'static>()->Parser<T, (), Index, Tran> {
+pub fn eof<T:'static+Display, Index:Debug+Display+'static, Tran:'static>()->Parser<T, (), Index, Tran> {
     abc!(|state: &mut State<T, Index=Index, Tran=Tran>|->Status<(), Index> {
+        let pos = state.pos();
         let val = state.next();
         if val.is_none() {
             Ok(())
         } else {
-            let pos = state.pos();
             let description = format!("expect eof at {} but got value {}", pos, val.unwrap());
             Err(ParsecError::new(pos, description))
         }
     })
 }
 
-pub fn one_of<T:Eq+Debug+Display+Clone+'static, Index:Reflect+Debug+Display+'static, Tran:'static>(elements:&[T])
-            -> Parser<T, T, Index, Tran> {
-    let elements = elements.to_owned();
+pub fn one_of<T:Display+Clone+'static, S, Index:Debug+Display+'static, Tran:'static>(elements:S)
+            -> Parser<T, T, Index, Tran> where S:IntoSet<T> {
+    let elements = elements.into_set();
     abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<T, Index>{
+        let pos = state.pos();
         let next = state.next();
         if next.is_none() {
-            Err(ParsecError::new(state.pos(), String::from("eof")))
+            Err(ParsecError::new(pos, String::from("eof")))
         } else {
             let it = next.unwrap();
-            for d in &elements {
-                if d == &it {
-                    return Ok(it);
-                }
+            if elements.contains(&it) {
+                return Ok(it);
             }
-            let description = format!("<expect one of {:?} at {}, got:{}>", elements, state.pos(), it);
-            Err(ParsecError::new(state.pos(), String::from(description)))
+            let description = format!("<expect one of {} at {}, got:{}>", elements.describe(), pos, it);
+            Err(ParsecError::new(pos, String::from(description)))
         }
     })
 }
 
-pub fn none_of<T:Eq+Debug+Display+Clone+'static, Index:Reflect+Debug+Display+'static, Tran:'static>(elements:&[T]) -> Parser<T, T, Index, Tran> {
-    let elements = elements.to_owned();
+pub fn none_of<T:Display+Clone+'static, S, Index:Debug+Display+'static, Tran:'static>(elements:S)
+            -> Parser<T, T, Index, Tran> where S:IntoSet<T> {
+    let elements = elements.into_set();
     abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<T, Index> {
+        let pos = state.pos();
         let next = state.next();
         if next.is_none() {
-            Err(ParsecError::new(state.pos(), String::from("eof")))
+            Err(ParsecError::new(pos, String::from("eof")))
         } else {
             let it = next.unwrap();
-            for d in &elements {
-                if d == &it {
-                    let description = format!("<expect none of {:?} at {}, got:{}>", elements, state.pos(), it);
-                    return Err(ParsecError::new(state.pos(), String::from(description)))
-                }
+            if elements.contains(&it) {
+                let description = format!("<expect none of {} at {}, got:{}>", elements.describe(), pos, it);
+                return Err(ParsecError::new(pos, String::from(description)))
             }
             Ok(it)
         }
     })
 }
 
-pub fn pack<T, R:Clone+'static, Index:Reflect+Debug+'static, Tran:'static>(element:R) -> Parser<T, R, Index, Tran> {
+pub fn tag<T:Eq+Debug+Clone+'static, Index:Debug+Display+'static, Tran:'static>(elements:&[T])
+            -> Parser<T, Vec<T>, Index, Tran> {
+    let elements = elements.to_owned();
+    abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<Vec<T>, Index>{
+        let tran = state.begin();
+        for d in &elements {
+            let next = state.next();
+            if next.as_ref() != Some(d) {
+                state.rollback(tran);
+                let description = format!("<expect {:?} at {}, got:{:?}>", elements, state.pos(), next);
+                return Err(ParsecError::new(state.pos(), description));
+            }
+        }
+        state.commit(tran);
+        Ok(elements.clone())
+    })
+}
+
+pub fn string<Index:Debug+Display+'static, Tran:'static>(literal:&str)
+            -> Parser<char, String, Index, Tran> {
+    literal_by(literal, |x:&char, y:&char| x == y)
+}
+
+pub fn string_ci<Index:Debug+Display+'static, Tran:'static>(literal:&str)
+            -> Parser<char, String, Index, Tran> {
+    literal_by(literal, |x:&char, y:&char| x.to_lowercase().eq(y.to_lowercase()))
+}
+
+// Match the whole literal or rollback to where we started, so a partial match consumes nothing.
+fn literal_by<Index:Debug+Display+'static, Tran:'static, F:'static>(literal:&str, same:F)
+            -> Parser<char, String, Index, Tran> where F:Fn(&char, &char)->bool {
+    let literal = String::from(literal);
+    abc!(move |state: &mut State<char, Index=Index, Tran=Tran>|->Status<String, Index>{
+        let tran = state.begin();
+        let mut re = String::with_capacity(literal.len());
+        for c in literal.chars() {
+            match state.next() {
+                Some(x) if same(&c, &x) => re.push(x),
+                next => {
+                    state.rollback(tran);
+                    let description = format!("<expect {:?} at {}, got:{:?}>", literal, state.pos(), next);
+                    return Err(ParsecError::new(state.pos(), description));
+                }
+            }
+        }
+        state.commit(tran);
+        Ok(re)
+    })
+}
+
+pub fn satisfy<T:'static, Index:Debug+'static, Tran:'static, F:'static>(pred:F)
+            -> Parser<T, T, Index, Tran> where F:Fn(&T)->bool {
+    abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<T, Index>{
+        state.next_by(&pred)
+    })
+}
+
+pub fn take_while<T:'static, Index:Debug+Display+'static, Tran:'static, F:'static>(pred:F)
+            -> Parser<T, Vec<T>, Index, Tran> where T:Clone, F:Fn(&T)->bool {
+    take_while_m_n(0, usize::max_value(), pred)
+}
+
+pub fn take_while1<T:'static, Index:Debug+Display+'static, Tran:'static, F:'static>(pred:F)
+            -> Parser<T, Vec<T>, Index, Tran> where T:Clone, F:Fn(&T)->bool {
+    take_while_m_n(1, usize::max_value(), pred)
+}
+
+pub fn take_till<T:'static, Index:Debug+Display+'static, Tran:'static, F:'static>(pred:F)
+            -> Parser<T, Vec<T>, Index, Tran> where T:Clone, F:Fn(&T)->bool {
+    take_while(move |x:&T| !pred(x))
+}
+
+pub fn take_while_m_n<T:'static, Index:Debug+Display+'static, Tran:'static, F:'static>(m:usize, n:usize, pred:F)
+            -> Parser<T, Vec<T>, Index, Tran> where T:Clone, F:Fn(&T)->bool {
+    abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<Vec<T>, Index>{
+        let start = state.begin();
+        let mut re = Vec::new();
+        while re.len() < n {
+            match state.next_by(&pred) {
+                Ok(x) => re.push(x),
+                Err(_) => break,
+            }
+        }
+        if re.len() < m {
+            state.rollback(start);
+            let description = format!("<expect at least {} matched elements at {}, got:{}>", m, state.pos(), re.len());
+            return Err(ParsecError::new(state.pos(), description));
+        }
+        state.commit(start);
+        Ok(re)
+    })
+}
+
+pub fn pack<T, R:Clone+'static, Index:Debug+'static, Tran:'static>(element:R) -> Parser<T, R, Index, Tran> {
     abc!(move |_: &mut State<T, Index=Index, Tran=Tran>|->Status<R, Index>{
         Ok(element.clone())
     })
 }
 
-pub fn fail<T:'static+Clone, R, Index:Reflect+Debug+Display+'static, Tran:'static>(description:String) -> Parser<T, R, Index, Tran> {
+pub fn fail<T:'static+Clone, R, Index:Debug+Display+'static, Tran:'static>(description:String) -> Parser<T, R, Index, Tran> {
     abc!(move |state:&mut State<T, Index=Index, Tran=Tran>|->Status<R, Index>{
         Err(ParsecError::new(state.pos(), String::from(description.as_str())))
     })
@@ -0,0 +1,237 @@
+use std::fmt::{Formatter, Debug, Display};
+use std::fmt;
+use std::ops::RangeInclusive;
+
+// The sets one_of/none_of test elements against.
+pub trait Set<T> {
+    fn contains(&self, item:&T)->bool;
+    fn describe(&self)->String;
+}
+
+pub trait IntoSet<T> {
+    type Set:Set<T>+'static;
+    fn into_set(self)->Self::Set;
+}
+
+impl<T:Eq+Debug> Set<T> for Vec<T> {
+    fn contains(&self, item:&T)->bool {
+        self.iter().any(|x| x == item)
+    }
+    fn describe(&self)->String {
+        format!("{:?}", self)
+    }
+}
+
+impl<T:Eq+Debug+'static> IntoSet<T> for Vec<T> {
+    type Set = Vec<T>;
+    fn into_set(self)->Vec<T> {
+        self
+    }
+}
+
+impl<'a, T:Eq+Debug+Clone+'static> IntoSet<T> for &'a Vec<T> {
+    type Set = Vec<T>;
+    fn into_set(self)->Vec<T> {
+        self.clone()
+    }
+}
+
+impl<'a, T:Eq+Debug+Clone+'static> IntoSet<T> for &'a [T] {
+    type Set = Vec<T>;
+    fn into_set(self)->Vec<T> {
+        self.to_owned()
+    }
+}
+
+impl<'a, T:Eq+Debug+Clone+'static, const N:usize> IntoSet<T> for &'a [T; N] {
+    type Set = Vec<T>;
+    fn into_set(self)->Vec<T> {
+        self.to_vec()
+    }
+}
+
+// Classes backed by the Unicode properties std exposes on char. These are binary properties,
+// not general categories: std has no tables for Lu, Nd or P*, so punctuation and the finer
+// letter and number classes are out of scope. Numeric is any N*, not only decimal digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Alphabetic,
+    Lowercase,
+    Uppercase,
+    Numeric,
+    Alphanumeric,
+    Whitespace,
+    Control,
+}
+
+impl Category {
+    pub fn contains(&self, c:char)->bool {
+        match *self {
+            Category::Alphabetic => c.is_alphabetic(),
+            Category::Lowercase => c.is_lowercase(),
+            Category::Uppercase => c.is_uppercase(),
+            Category::Numeric => c.is_numeric(),
+            Category::Alphanumeric => c.is_alphanumeric(),
+            Category::Whitespace => c.is_whitespace(),
+            Category::Control => c.is_control(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    Range(char, char),
+    Category(Category),
+    Set(CharSet),
+}
+
+impl Item {
+    fn contains(&self, c:char)->bool {
+        match *self {
+            Item::Range(lo, hi) => lo <= c && c <= hi,
+            Item::Category(ref cat) => cat.contains(c),
+            Item::Set(ref set) => set.contains(&c),
+        }
+    }
+}
+
+// Items are the description of the set, ascii caches their answer for the first 128 code points.
+#[derive(Debug, Clone)]
+pub struct CharSet {
+    ascii: [u64; 2],
+    items: Vec<Item>,
+    negated: bool,
+}
+
+impl CharSet {
+    pub fn new()->CharSet {
+        CharSet{ascii:[0, 0], items:Vec::new(), negated:false}
+    }
+
+    pub fn range(range:RangeInclusive<char>)->CharSet {
+        CharSet::new().with_range(range)
+    }
+
+    pub fn chars(chars:&str)->CharSet {
+        CharSet::new().with_chars(chars)
+    }
+
+    pub fn category(category:Category)->CharSet {
+        CharSet::new().with_category(category)
+    }
+
+    pub fn with_range(self, range:RangeInclusive<char>)->CharSet {
+        let (lo, hi) = (*range.start(), *range.end());
+        self.with_item(Item::Range(lo, hi))
+    }
+
+    pub fn with_chars(self, chars:&str)->CharSet {
+        chars.chars().fold(self, |set, c| set.with_item(Item::Range(c, c)))
+    }
+
+    pub fn with_category(self, category:Category)->CharSet {
+        self.with_item(Item::Category(category))
+    }
+
+    pub fn union(self, other:CharSet)->CharSet {
+        if !self.negated && !other.negated {
+            return other.items.into_iter().fold(self, |set, item| set.with_item(item));
+        }
+        let mut re = CharSet::new();
+        re.ascii = [self.ascii[0] | other.ascii[0], self.ascii[1] | other.ascii[1]];
+        re.items.push(Item::Set(self));
+        re.items.push(Item::Set(other));
+        re
+    }
+
+    pub fn negate(self)->CharSet {
+        CharSet{
+            ascii: [!self.ascii[0], !self.ascii[1]],
+            items: self.items,
+            negated: !self.negated,
+        }
+    }
+
+    fn with_item(mut self, item:Item)->CharSet {
+        for code in 0..128u8 {
+            if item.contains(code as char) {
+                let bit = 1 << (code % 64);
+                if self.negated {
+                    self.ascii[(code / 64) as usize] &= !bit;
+                } else {
+                    self.ascii[(code / 64) as usize] |= bit;
+                }
+            }
+        }
+        self.items.push(item);
+        self
+    }
+}
+
+impl Set<char> for CharSet {
+    fn contains(&self, item:&char)->bool {
+        let code = *item as u32;
+        if code < 128 {
+            self.ascii[(code / 64) as usize] & (1 << (code % 64)) != 0
+        } else {
+            self.items.iter().any(|x| x.contains(*item)) != self.negated
+        }
+    }
+    fn describe(&self)->String {
+        format!("{}", self)
+    }
+}
+
+impl IntoSet<char> for CharSet {
+    type Set = CharSet;
+    fn into_set(self)->CharSet {
+        self
+    }
+}
+
+impl<'a> IntoSet<char> for &'a CharSet {
+    type Set = CharSet;
+    fn into_set(self)->CharSet {
+        self.clone()
+    }
+}
+
+impl IntoSet<char> for RangeInclusive<char> {
+    type Set = CharSet;
+    fn into_set(self)->CharSet {
+        CharSet::range(self)
+    }
+}
+
+impl<'a> IntoSet<char> for &'a str {
+    type Set = CharSet;
+    fn into_set(self)->CharSet {
+        CharSet::chars(self)
+    }
+}
+
+fn escape(c:char)->String {
+    match c {
+        ']' | '[' | '\\' | '^' | '-' => format!("\\{}", c),
+        _ if c.is_control() || c.is_whitespace() => c.escape_default().collect(),
+        _ => c.to_string(),
+    }
+}
+
+impl Display for CharSet {
+    fn fmt(&self, formatter:&mut Formatter) -> Result<(), fmt::Error> {
+        try!(write!(formatter, "["));
+        if self.negated {
+            try!(write!(formatter, "^"));
+        }
+        for item in &self.items {
+            match *item {
+                Item::Range(lo, hi) if lo == hi => try!(write!(formatter, "{}", escape(lo))),
+                Item::Range(lo, hi) => try!(write!(formatter, "{}-{}", escape(lo), escape(hi))),
+                Item::Category(ref cat) => try!(write!(formatter, "\\p{{{:?}}}", cat)),
+                Item::Set(ref set) => try!(write!(formatter, "{}", set)),
+            }
+        }
+        write!(formatter, "]")
+    }
+}
@@ -0,0 +1,383 @@
+use parsec::{Parser, Error, parse_complete};
+use std::fmt::{Formatter, Debug, Display};
+use std::fmt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+// Property checks for grammars in unit tests, after Haskell's QuickCheck: generate values of
+// growing size, run the property on each, and shrink the first failing value to a small one.
+//   check(&Config::new(), |x:&Vec<i64>| round_trip(&list, &print, x))
+
+// SplitMix64, small and seedable, so a failing run replays the same on every platform.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed:u64)->Rng {
+        Rng{state:seed}
+    }
+
+    pub fn next_u64(&mut self)->u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in 0..n, n must not be 0.
+    pub fn below(&mut self, n:usize)->usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    // Uniform in low..=high.
+    pub fn range(&mut self, low:i64, high:i64)->i64 {
+        let width = high.wrapping_sub(low) as u64;
+        if width == u64::max_value() {
+            return self.next_u64() as i64;
+        }
+        low.wrapping_add((self.next_u64() % (width + 1)) as i64)
+    }
+
+    // Uniform in [0, 1).
+    pub fn unit(&mut self)->f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // True once in n draws on average.
+    pub fn one_in(&mut self, n:usize)->bool {
+        self.below(n) == 0
+    }
+
+    pub fn pick<'a, T>(&mut self, items:&'a [T])->&'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+// Values with a generator and a shrinker. size bounds lengths and magnitudes, it grows over
+// the cases of a run so the simple values come first.
+pub trait Arbitrary: Clone+Debug+'static {
+    fn arbitrary(rng:&mut Rng, size:usize)->Self;
+
+    // Smaller candidates, the most aggressive first.
+    fn shrink(&self)->Vec<Self> {
+        Vec::new()
+    }
+}
+
+// Candidates between 0 and x, halving the distance each step, then x one step closer to 0.
+fn shrink_toward_zero(x:i64)->Vec<i64> {
+    let mut re = Vec::new();
+    if x == 0 {
+        return re;
+    }
+    re.push(0);
+    let mut d = x / 2;
+    while d != 0 {
+        let y = x - d;
+        if y != 0 && !re.contains(&y) {
+            re.push(y);
+        }
+        d /= 2;
+    }
+    let y = if x > 0 { x - 1 } else { x + 1 };
+    if !re.contains(&y) {
+        re.push(y);
+    }
+    re
+}
+
+macro_rules! signed_arbitrary {
+    ($($t:ty),*) => {$(
+        impl Arbitrary for $t {
+            fn arbitrary(rng:&mut Rng, size:usize)->$t {
+                let bound = (size as i64).min(<$t>::max_value() as i64);
+                rng.range(-bound, bound) as $t
+            }
+
+            fn shrink(&self)->Vec<$t> {
+                shrink_toward_zero(*self as i64).into_iter().map(|x| x as $t).collect()
+            }
+        }
+    )*}
+}
+
+macro_rules! unsigned_arbitrary {
+    ($($t:ty),*) => {$(
+        impl Arbitrary for $t {
+            fn arbitrary(rng:&mut Rng, size:usize)->$t {
+                let bound = (size as u64).min(<$t>::max_value() as u64);
+                rng.range(0, bound as i64) as $t
+            }
+
+            fn shrink(&self)->Vec<$t> {
+                if *self as u64 > i64::max_value() as u64 {
+                    return vec![0, *self / 2];
+                }
+                shrink_toward_zero(*self as i64).into_iter().map(|x| x as $t).collect()
+            }
+        }
+    )*}
+}
+
+signed_arbitrary!(i8, i16, i32, i64, isize);
+unsigned_arbitrary!(u8, u16, u32, u64, usize);
+
+impl Arbitrary for f64 {
+    fn arbitrary(rng:&mut Rng, size:usize)->f64 {
+        (rng.unit() * 2.0 - 1.0) * size as f64
+    }
+
+    fn shrink(&self)->Vec<f64> {
+        if *self == 0.0 {
+            return Vec::new();
+        }
+        let mut re = vec![0.0];
+        if self.trunc() != *self {
+            re.push(self.trunc());
+        }
+        re
+    }
+}
+
+impl Arbitrary for f32 {
+    fn arbitrary(rng:&mut Rng, size:usize)->f32 {
+        f64::arbitrary(rng, size) as f32
+    }
+
+    fn shrink(&self)->Vec<f32> {
+        (*self as f64).shrink().into_iter().map(|x| x as f32).collect()
+    }
+}
+
+impl Arbitrary for bool {
+    fn arbitrary(rng:&mut Rng, _:usize)->bool {
+        rng.one_in(2)
+    }
+
+    fn shrink(&self)->Vec<bool> {
+        if *self { vec![false] } else { Vec::new() }
+    }
+}
+
+// Mostly lower case letters, sometimes any printable ASCII, rarely anything at all.
+impl Arbitrary for char {
+    fn arbitrary(rng:&mut Rng, _:usize)->char {
+        match rng.below(10) {
+            0 => loop {
+                if let Some(c) = ::std::char::from_u32(rng.below(0x110000) as u32) {
+                    return c;
+                }
+            },
+            1 | 2 | 3 => (b' ' + rng.below(95) as u8) as char,
+            _ => (b'a' + rng.below(26) as u8) as char,
+        }
+    }
+
+    fn shrink(&self)->Vec<char> {
+        ['a', 'b', 'c', 'A', '0', ' '].iter().cloned().take_while(|c| c != self).collect()
+    }
+}
+
+impl<T:Arbitrary> Arbitrary for Vec<T> {
+    fn arbitrary(rng:&mut Rng, size:usize)->Vec<T> {
+        let len = rng.below(size + 1);
+        (0..len).map(|_| T::arbitrary(rng, size)).collect()
+    }
+
+    // Drop chunks of halving length, then shrink one element at a time.
+    fn shrink(&self)->Vec<Vec<T>> {
+        let mut re = Vec::new();
+        let mut chunk = self.len();
+        while chunk > 0 {
+            let mut start = 0;
+            while start < self.len() {
+                let end = (start + chunk).min(self.len());
+                let mut x = self[..start].to_vec();
+                x.extend_from_slice(&self[end..]);
+                re.push(x);
+                start = end;
+            }
+            chunk /= 2;
+        }
+        for (i, item) in self.iter().enumerate() {
+            for smaller in item.shrink() {
+                let mut x = self.clone();
+                x[i] = smaller;
+                re.push(x);
+            }
+        }
+        re
+    }
+}
+
+impl Arbitrary for String {
+    fn arbitrary(rng:&mut Rng, size:usize)->String {
+        Vec::<char>::arbitrary(rng, size).into_iter().collect()
+    }
+
+    fn shrink(&self)->Vec<String> {
+        self.chars().collect::<Vec<char>>().shrink().into_iter().map(|x| x.into_iter().collect()).collect()
+    }
+}
+
+impl<T:Arbitrary> Arbitrary for Option<T> {
+    fn arbitrary(rng:&mut Rng, size:usize)->Option<T> {
+        if rng.one_in(4) { None } else { Some(T::arbitrary(rng, size)) }
+    }
+
+    fn shrink(&self)->Vec<Option<T>> {
+        match *self {
+            Some(ref x) => {
+                let mut re = vec![None];
+                re.extend(x.shrink().into_iter().map(Some));
+                re
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+impl<T:Arbitrary> Arbitrary for Box<T> {
+    fn arbitrary(rng:&mut Rng, size:usize)->Box<T> {
+        Box::new(T::arbitrary(rng, size))
+    }
+
+    fn shrink(&self)->Vec<Box<T>> {
+        (**self).shrink().into_iter().map(Box::new).collect()
+    }
+}
+
+impl<A:Arbitrary, B:Arbitrary> Arbitrary for (A, B) {
+    fn arbitrary(rng:&mut Rng, size:usize)->(A, B) {
+        (A::arbitrary(rng, size), B::arbitrary(rng, size))
+    }
+
+    fn shrink(&self)->Vec<(A, B)> {
+        let mut re = self.0.shrink().into_iter().map(|a| (a, self.1.clone())).collect::<Vec<(A, B)>>();
+        re.extend(self.1.shrink().into_iter().map(|b| (self.0.clone(), b)));
+        re
+    }
+}
+
+impl<A:Arbitrary, B:Arbitrary, C:Arbitrary> Arbitrary for (A, B, C) {
+    fn arbitrary(rng:&mut Rng, size:usize)->(A, B, C) {
+        (A::arbitrary(rng, size), B::arbitrary(rng, size), C::arbitrary(rng, size))
+    }
+
+    fn shrink(&self)->Vec<(A, B, C)> {
+        let mut re = self.0.shrink().into_iter().map(|a| (a, self.1.clone(), self.2.clone())).collect::<Vec<(A, B, C)>>();
+        re.extend(self.1.shrink().into_iter().map(|b| (self.0.clone(), b, self.2.clone())));
+        re.extend(self.2.shrink().into_iter().map(|c| (self.0.clone(), self.1.clone(), c)));
+        re
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub cases: usize,
+    pub seed: u64,
+    // Size of the last case, the first ones are smaller.
+    pub max_size: usize,
+    // Limit of property runs spent on shrinking a failure.
+    pub max_shrinks: usize,
+}
+
+impl Config {
+    pub fn new()->Config {
+        Config{cases:100, seed:0x5eed, max_size:32, max_shrinks:1000}
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Failure<T> {
+    pub seed: u64,
+    pub case: usize,
+    pub original: T,
+    pub shrunk: T,
+    pub shrinks: usize,
+    // What the property said about the shrunk value.
+    pub message: String,
+}
+
+impl<T:Debug> Display for Failure<T> {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        write!(formatter, "property failed at case {} of seed {:#x} after {} shrinks: {}\n  original: {:?}\n  shrunk: {:?}",
+            self.case, self.seed, self.shrinks, self.message, self.original, self.shrunk)
+    }
+}
+
+// A panic in the property is a failure too, with the panic message.
+fn run<T, P>(prop:&P, x:&T)->Result<(), String> where P:Fn(&T)->Result<(), String> {
+    match catch_unwind(AssertUnwindSafe(|| prop(x))) {
+        Ok(re) => re,
+        Err(payload) => Err(match payload.downcast_ref::<&str>() {
+            Some(message) => format!("panic: {}", message),
+            None => match payload.downcast_ref::<String>() {
+                Some(message) => format!("panic: {}", message),
+                None => String::from("panic"),
+            },
+        }),
+    }
+}
+
+// Runs prop on config.cases values from gen and returns the number of cases, or the first
+// failure shrunk greedily: take the first candidate that still fails until none does.
+pub fn check_by<T, G, S, P>(config:&Config, gen:G, shrink:S, prop:P)->Result<usize, Failure<T>>
+where T:Clone+Debug, G:Fn(&mut Rng, usize)->T, S:Fn(&T)->Vec<T>, P:Fn(&T)->Result<(), String> {
+    let mut rng = Rng::new(config.seed);
+    for case in 0..config.cases {
+        let size = config.max_size * (case + 1) / config.cases;
+        let original = gen(&mut rng, size);
+        let mut message = match run(&prop, &original) {
+            Ok(()) => continue,
+            Err(message) => message,
+        };
+        let mut shrunk = original.clone();
+        let mut shrinks = 0;
+        let mut budget = config.max_shrinks;
+        'shrinking: while budget > 0 {
+            for candidate in shrink(&shrunk) {
+                if budget == 0 {
+                    break 'shrinking;
+                }
+                budget -= 1;
+                if let Err(m) = run(&prop, &candidate) {
+                    shrunk = candidate;
+                    message = m;
+                    shrinks += 1;
+                    continue 'shrinking;
+                }
+            }
+            break;
+        }
+        return Err(Failure{seed:config.seed, case:case, original:original, shrunk:shrunk, shrinks:shrinks, message:message});
+    }
+    Ok(config.cases)
+}
+
+pub fn check<T, P>(config:&Config, prop:P)->Result<usize, Failure<T>>
+where T:Arbitrary, P:Fn(&T)->Result<(), String> {
+    check_by(config, T::arbitrary, T::shrink, prop)
+}
+
+// The round trip property parse(print(x)) == x, the whole printed text must be consumed.
+pub fn round_trip<T, F>(parser:&Parser<char, T, usize, usize>, print:F, x:&T)->Result<(), String>
+where T:PartialEq+Clone+Debug+'static, F:Fn(&T)->String {
+    let text = print(x);
+    match parse_complete(parser.clone(), text.as_str()).into_result() {
+        Ok(ref y) if y == x => Ok(()),
+        Ok(y) => Err(format!("{:?} printed as {:?} parsed back as {:?}", x, text, y)),
+        Err(err) => Err(format!("{:?} printed as {:?} failed at {:?}: {}", x, text, err.pos(), err)),
+    }
+}
+
+// For #[test] functions: checks the round trip over Arbitrary values and panics with the
+// shrunk counterexample.
+pub fn assert_round_trip<T, F>(config:&Config, parser:Parser<char, T, usize, usize>, print:F)
+where T:Arbitrary+PartialEq, F:Fn(&T)->String {
+    if let Err(failure) = check(config, |x:&T| round_trip(&parser, &print, x)) {
+        panic!("{}", failure);
+    }
+}
@@ -1,11 +1,14 @@
 use parsec::{State, Status, Monad, Parser, Parsec};
 use parsec::atom::{pack, fail};
+use parsec::memo::{Entry, next_id};
 use std::fmt::{Debug, Display};
-use std::sync::Arc;
-use std::marker::Reflect;
+use std::sync::{Arc, Weak};
 use std::cmp::PartialEq;
+use std::hash::Hash;
+use std::cell::RefCell;
+use std::rc::Rc;
 
-pub fn try<T:'static, R:'static, X:'static, Index:Reflect+Debug+'static, Tran:'static>
+pub fn try<T:'static, R:'static, X:'static, Index:Debug+'static, Tran:'static>
         (p:X)->Parser<T, R, Index, Tran>
 where T:Clone, R:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
     abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<R, Index>{
@@ -20,12 +23,12 @@ where T:Clone, R:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
     })
 }
 
-pub trait Or<T, R, Index:Reflect+Debug, Tran> {
+pub trait Or<T, R, Index:Debug, Tran> {
     fn or(&self, Parser<T, R, Index, Tran>)->Parser<T, R, Index, Tran>;
 }
 
 pub type Either<T, R, Index:'static, Tran:'static> = Arc<Box<Fn(&mut State<T, Index=Index, Tran=Tran>)->Status<R, Index>>>;
-pub fn either<T, R, X:'static, Y:'static, Index:PartialEq+Reflect+Debug+'static, Tran:'static>
+pub fn either<T, R, X:'static, Y:'static, Index:PartialEq+Debug+'static, Tran:'static>
         (x:X, y:Y)->Either<T, R, Index, Tran>
 where T:Clone, R:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone,
             Y:Parsec<T, R, Index=Index, Tran=Tran>+Clone{
@@ -45,22 +48,28 @@ where T:Clone, R:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone,
         }
     })
 }
-impl<T:'static+Clone, R:'static+Clone, Index:PartialEq+Reflect+Debug+'static, Tran:'static> Or<T, R, Index, Tran> for Either<T, R, Index, Tran> {
+impl<T:'static+Clone, R:'static+Clone, Index:PartialEq+Debug+'static, Tran:'static> Or<T, R, Index, Tran> for Either<T, R, Index, Tran> {
     fn or(&self, p:Parser<T, R, Index, Tran>)->Parser<T, R, Index, Tran>{
         let s:Parser<T, R, Index, Tran> = self.clone();
         either(s, p)
     }
 }
 
-pub fn many<T:'static, R:'static, X:'static, Index:Reflect+Debug+'static, Tran:'static>(p:X)->Parser<T, Vec<R>, Index, Tran>
+// Repetitions stop after a round that succeeds without consuming anything, so many(optional(p))
+// or many(look_ahead(p)) end instead of looping forever.
+pub fn many<T:'static, R:'static, X:'static, Index:PartialEq+Debug+'static, Tran:'static>(p:X)->Parser<T, Vec<R>, Index, Tran>
 where T:Clone, R:Clone+Debug, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
     let p=try(p.clone());
     abc!(move |state:&mut State<T, Index=Index, Tran=Tran>|->Status<Vec<R>, Index>{
         let mut re = Vec::<R>::new();
         loop {
+            let pos = state.pos();
             let r = p.parse(state);
             if r.is_ok() {
                 re.push(r.unwrap());
+                if state.pos() == pos {
+                    break;
+                }
             } else {
                 break;
             }
@@ -69,17 +78,25 @@ where T:Clone, R:Clone+Debug, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
     })
 }
 
-pub fn many1<T:'static, R:'static, X:'static, Index:Reflect+Debug+'static, Tran:'static>(p:X)->Parser<T, Vec<R>, Index, Tran>
+pub fn many1<T:'static, R:'static, X:'static, Index:PartialEq+Debug+'static, Tran:'static>(p:X)->Parser<T, Vec<R>, Index, Tran>
 where T:Clone, R:Clone+Debug, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
     abc!(move |state:&mut State<T, Index=Index, Tran=Tran>|->Status<Vec<R>, Index>{
+        let pos = state.pos();
         let first = try!(p.parse(state));
         let mut re = Vec::new();
         re.push(first);
+        if state.pos() == pos {
+            return Ok(re);
+        }
         let psc = try(p.clone());
         loop {
+            let pos = state.pos();
             let r = psc.parse(state);
             if r.is_ok() {
                 re.push(r.unwrap());
+                if state.pos() == pos {
+                    break;
+                }
             } else {
                 break;
             }
@@ -89,7 +106,7 @@ where T:Clone, R:Clone+Debug, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
 }
 
 pub fn between<T:'static, B:'static, P:'static, E:'static, X:'static, Open:'static, Close:'static,
-            Index:Reflect+Debug+'static, Tran:'static>
+            Index:Debug+'static, Tran:'static>
         (open:Open, close:Close, parsec:X)
         ->Parser<T, P, Index, Tran>
 where T:Clone, P:Clone, B:Clone, E:Clone, Open:Monad<T, B, Index=Index, Tran=Tran>+Clone,
@@ -106,14 +123,16 @@ where T:Clone, P:Clone, B:Clone, E:Clone, Open:Monad<T, B, Index=Index, Tran=Tra
     })
 }
 
-pub fn otherwise<T:'static, R:'static, X:'static, Index:PartialEq+Reflect+Debug+Display+'static, Tran:'static>(p:X, description:String)->Parser<T, R, Index, Tran>
+pub fn otherwise<T:'static, R:'static, X:'static, Index:PartialEq+Debug+Display+'static, Tran:'static>(p:X, description:String)->Parser<T, R, Index, Tran>
 where T:Clone, R:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
     abc!(move |state : &mut State<T, Index=Index, Tran=Tran>|->Status<R, Index>{
         either(p.clone(), fail(description.clone()).clone()).parse(state)
     })
 }
 
-pub fn many_till<T:'static, R:'static, Tl:'static, X:'static, Till:'static, Index:Reflect+Debug+'static, Tran:'static>
+// Fails if p matches without consuming anything while till does not match, since every later
+// round would do the same.
+pub fn many_till<T:'static, R:'static, Tl:'static, X:'static, Till:'static, Index:PartialEq+Debug+'static, Tran:'static>
     (p:X, till:Till)->Parser<T, Vec<R>, Index, Tran>
 where T:Clone, R:Clone+Debug, Tl:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone,
             Till:Parsec<T, Tl, Index=Index, Tran=Tran>+Clone{
@@ -126,29 +145,64 @@ where T:Clone, R:Clone+Debug, Tl:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+C
             if stop.is_ok() {
                 return Ok(re.clone());
             } else {
+                let pos = state.pos();
                 let item = try!(p.parse(state));
+                if state.pos() == pos {
+                    return Err(state.err(String::from("many_till item consumed nothing")));
+                }
                 re.push(item);
             }
         }
     })
 }
 
+// Run p without consuming anything, whether it succeeds or fails.
+pub fn look_ahead<T:'static, R:'static, X:'static, Index:Debug+'static, Tran:'static>
+        (p:X)->Parser<T, R, Index, Tran>
+where T:Clone, R:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
+    abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<R, Index>{
+        let tran = state.begin();
+        let re = p.parse(state);
+        state.rollback(tran);
+        re
+    })
+}
+
+// Succeed without consuming anything when p fails here, fail when p would match.
+pub fn not_followed_by<T:'static, R:'static, X:'static, Index:Debug+'static, Tran:'static>
+        (p:X)->Parser<T, (), Index, Tran>
+where T:Clone, R:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
+    abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<(), Index>{
+        let tran = state.begin();
+        let re = p.parse(state);
+        state.rollback(tran);
+        match re {
+            Ok(_) => Err(state.err(String::from("unexpected input"))),
+            Err(_) => Ok(()),
+        }
+    })
+}
+
 // We can use many/many1 as skip, but them more effective.
-pub fn skip<T:'static, R:'static, X:'static, Index:PartialEq+Reflect+Debug+'static, Tran:'static>
+pub fn skip<T:'static, R:'static, X:'static, Index:PartialEq+Debug+'static, Tran:'static>
         (p:X) ->Parser<T, Vec<R>, Index, Tran>
 where T:Clone, R:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
     abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<Vec<R>, Index>{
         let p = try(p.clone());
         loop {
+            let pos = state.pos();
             let re = p.parse(state);
             if re.is_err() {
                 return Ok(Vec::new());
             }
+            if state.pos() == pos {
+                return Ok(Vec::new());
+            }
         }
     })
 }
 
-pub fn skip1<T:'static, R:'static, X:'static, Index:PartialEq+Reflect+Debug+'static, Tran:'static>
+pub fn skip1<T:'static, R:'static, X:'static, Index:PartialEq+Debug+'static, Tran:'static>
         (p:X) ->Parser<T, Vec<R>, Index, Tran>
 where T:Clone, R:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
     abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<Vec<R>, Index>{
@@ -157,7 +211,7 @@ where T:Clone, R:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
     })
 }
 
-pub fn sep_by<T:'static, Sp:'static, R:'static, Sep:'static, X:'static, Index:PartialEq+Reflect+Debug+'static, Tran:'static>
+pub fn sep_by<T:'static, Sp:'static, R:'static, Sep:'static, X:'static, Index:PartialEq+Debug+'static, Tran:'static>
         (parsec:X, sep:Sep)->Parser<T, Vec<R>, Index, Tran>
 where T:Clone, R:Clone+Debug, Sp:Clone, Sep:Parsec<T, Sp, Index=Index, Tran=Tran>+Clone,
             X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
@@ -168,7 +222,10 @@ where T:Clone, R:Clone+Debug, Sp:Clone, Sep:Parsec<T, Sp, Index=Index, Tran=Tran
     })
 }
 
-pub fn sep_by1<T:'static, Sp:'static, R:'static, Sep:'static, X:'static, Index:PartialEq+Reflect+Debug+'static, Tran:'static>
+// One or more p separated by sep. Each round reads sep and then p; a separator with no item
+// after it is rolled back and left for the caller, so "a,b," gives [a, b] and stops before
+// the last comma. Like many, it stops after a round that consumes nothing.
+pub fn sep_by1<T:'static, Sp:'static, R:'static, Sep:'static, X:'static, Index:PartialEq+Debug+'static, Tran:'static>
         (parsec:X, sep:Sep) ->Parser<T, Vec<R>, Index, Tran>
 where T:Clone, R:Clone+Debug, Sp:Clone, Sep:Parsec<T, Sp, Index=Index, Tran=Tran>+Clone,
         X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
@@ -179,11 +236,159 @@ where T:Clone, R:Clone+Debug, Sp:Clone, Sep:Parsec<T, Sp, Index=Index, Tran=Tran
             return Err(x.err().unwrap());
         }
         let mut rev = Vec::new();
-        let head = x.ok().unwrap();
-        let tail = sep_by(parsec.clone(), sep.clone()).parse(state);
-        let data = tail.unwrap();
-        rev.push(head);
-        rev.extend_from_slice(&data);
+        rev.push(x.ok().unwrap());
+        loop {
+            let pos = state.pos();
+            let tran = state.begin();
+            if sep.parse(state).is_err() {
+                state.rollback(tran);
+                break;
+            }
+            match parsec.parse(state) {
+                Ok(item) => {
+                    state.commit(tran);
+                    rev.push(item);
+                    if state.pos() == pos {
+                        break;
+                    }
+                }
+                Err(_) => {
+                    state.rollback(tran);
+                    break;
+                }
+            }
+        }
         Ok(rev)
     })
 }
+
+// Packrat rule: the result and end position of p at each start position are cached in the
+// state's memo table, so backtracking into the same rule replays instead of re-parsing.
+// States without a memo table just run p.
+pub fn memo<T:'static, R:'static, X:'static, Index:Hash+Eq+Clone+Debug+'static, Tran:'static>
+        (p:X)->Parser<T, R, Index, Tran>
+where T:Clone, R:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
+    let id = next_id();
+    abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<R, Index>{
+        let pos = state.pos();
+        let hit = state.memo().and_then(|table| table.get(id, pos.clone()).map(|entry| {
+            (entry.end.clone(), entry.value.downcast_ref::<Status<R, Index>>().unwrap().clone())
+        }));
+        if let Some((end, re)) = hit {
+            state.seek_to(end);
+            return re;
+        }
+        if let Some(table) = state.memo() {
+            table.enter(id, pos.clone());
+        }
+        let re = p.parse(state);
+        let end = state.pos();
+        if let Some(table) = state.memo() {
+            table.leave();
+            if !table.is_involved(id, &pos) {
+                table.insert(id, pos, Entry{end:end, value:Box::new(re.clone())});
+            }
+        }
+        re
+    })
+}
+
+// Left recursive rule, by seed growing on the memo table (Warth et al.): the first call at a
+// position fails as a seed, then p is re-run from that position while each round consumes more
+// than the last, and the longest result wins. Mark one rule in each left recursive cycle.
+// Only the rules the recursion runs through skip the table while it grows.
+pub fn left_rec<T:'static, R:'static, X:'static, Index:Hash+Eq+Clone+PartialOrd+Debug+'static, Tran:'static>
+        (p:X)->Parser<T, R, Index, Tran>
+where T:Clone, R:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
+    let id = next_id();
+    abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<R, Index>{
+        let pos = state.pos();
+        let hit = match state.memo() {
+            None => return Err(state.err(String::from("left_rec needs a state with memo table"))),
+            Some(table) => {
+                if table.is_growing(id, &pos) {
+                    table.involve(id, &pos);
+                }
+                table.get(id, pos.clone()).map(|entry| {
+                    (entry.end.clone(), entry.value.downcast_ref::<Status<R, Index>>().unwrap().clone())
+                })
+            }
+        };
+        if let Some((end, re)) = hit {
+            state.seek_to(end);
+            return re;
+        }
+        let seed:Status<R, Index> = Err(state.err(String::from("left recursion")));
+        if let Some(table) = state.memo() {
+            table.insert(id, pos.clone(), Entry{end:pos.clone(), value:Box::new(seed)});
+            table.start_growth(id, pos.clone());
+            table.enter(id, pos.clone());
+        }
+        let mut last = None;
+        loop {
+            state.seek_to(pos.clone());
+            let re = p.parse(state);
+            let end = state.pos();
+            let grown = match last {
+                None => true,
+                Some(ref last) => re.is_ok() && end > *last,
+            };
+            if !grown {
+                break;
+            }
+            let failed = re.is_err();
+            last = Some(end.clone());
+            if let Some(table) = state.memo() {
+                table.insert(id, pos.clone(), Entry{end:end, value:Box::new(re)});
+            }
+            if failed {
+                break;
+            }
+        }
+        let (end, re) = state.memo().map(|table| {
+            table.leave();
+            table.stop_growth();
+            let re = {
+                let entry = table.get(id, pos.clone()).unwrap();
+                (entry.end.clone(), entry.value.downcast_ref::<Status<R, Index>>().unwrap().clone())
+            };
+            // Grown on the seed of a rule still growing here, so it is only good for this round.
+            if table.is_involved(id, &pos) {
+                table.remove(id, pos.clone());
+            }
+            re
+        }).unwrap();
+        state.seek_to(end);
+        re
+    })
+}
+
+// Defer building the parser until it first runs, so a rule can mention itself, e.g.
+// fn value()->Parser<..> { either(number(), lazy(|| list_of(value()))) }
+pub fn lazy<T:'static, R:'static, Index:Debug+'static, Tran:'static, F:'static>(f:F)->Parser<T, R, Index, Tran>
+where T:Clone, R:Clone, F:Fn()->Parser<T, R, Index, Tran> {
+    let cell:RefCell<Option<Parser<T, R, Index, Tran>>> = RefCell::new(None);
+    abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<R, Index>{
+        let p = cell.borrow_mut().get_or_insert_with(|| f()).clone();
+        p.parse(state)
+    })
+}
+
+// Fixed point of a grammar rule, like functional::fixed::y but tying the knot through a weak
+// reference instead of unrolling, so every depth of recursion runs the same parser and memo or
+// left_rec inside it keep one identity.
+pub fn fix<T:'static, R:'static, Index:Debug+'static, Tran:'static, F>(f:F)->Parser<T, R, Index, Tran>
+where T:Clone, R:Clone, F:Fn(Parser<T, R, Index, Tran>)->Parser<T, R, Index, Tran> {
+    let knot:Rc<RefCell<Weak<Box<Fn(&mut State<T, Index=Index, Tran=Tran>)->Status<R, Index>>>>> = Rc::new(RefCell::new(Weak::new()));
+    let this = knot.clone();
+    let me:Parser<T, R, Index, Tran> = abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<R, Index>{
+        let p = this.borrow().upgrade();
+        match p {
+            Some(p) => p.parse(state),
+            None => Err(state.err(String::from("recursive parser has been dropped"))),
+        }
+    });
+    let re = f(me);
+    *knot.borrow_mut() = Arc::downgrade(&re);
+    re
+}
@@ -0,0 +1,156 @@
+use parsec::{State, Status, Parser, Parsec, Monad, parse_complete};
+use parsec::atom::{eq, one, one_of, none_of, string, satisfy, pack};
+use parsec::charset::CharSet;
+use parsec::combinator::{either, try, many, many1, sep_by, between, skip, lazy};
+use parsec::text::{integer, float};
+use std::str::FromStr;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+// Types that know their own grammar. #[derive(Parse)] from the ruskell_derive crate writes the
+// impl for structs and enums out of the impls here; every element skips leading spaces and tabs.
+pub trait Parse: Sized+Clone+Debug+'static {
+    fn parser()->Parser<char, Self, usize, usize>;
+
+    fn parse_str(text:&str)->Status<Self, usize> {
+        parse_complete(Self::parser().over(blank()), text).into_result()
+    }
+}
+
+// Vec fields marked with a separator in the derive attributes.
+pub trait Repeat: Parse {
+    fn separated(sep:&str)->Parser<char, Self, usize, usize>;
+}
+
+pub fn blank()->Parser<char, Vec<char>, usize, usize> {
+    skip(one_of(" \t"))
+}
+
+pub fn literal(text:&str)->Parser<char, (), usize, usize> {
+    blank().then(string(text)).then(pack(()))
+}
+
+// Parser of a field type, built on first use so recursive types do not build forever.
+pub fn field<R:Parse>()->Parser<char, R, usize, usize> {
+    lazy(R::parser)
+}
+
+// Alternatives in order, each one backtracking fully, as the variants of a derived enum.
+pub fn choice<R:Clone+'static>(alternatives:Vec<Parser<char, R, usize, usize>>)->Parser<char, R, usize, usize> {
+    abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<R, usize>{
+        let mut last = None;
+        for p in &alternatives {
+            let tran = state.begin();
+            match p.parse(state) {
+                Ok(value) => {
+                    state.commit(tran);
+                    return Ok(value);
+                }
+                Err(err) => {
+                    state.rollback(tran);
+                    last = Some(err);
+                }
+            }
+        }
+        Err(last.unwrap_or_else(|| state.err(String::from("no alternative"))))
+    })
+}
+
+fn number<R:FromStr+Clone+'static>(p:Parser<char, String, usize, usize>)->Parser<char, R, usize, usize>
+where R::Err:Display {
+    blank().then(p).bind(abc!(|text:String, state: &mut State<char, Index=usize, Tran=usize>|->Status<R, usize>{
+        text.parse::<R>().map_err(|e| state.err(format!("invalid number {}: {}", text, e)))
+    }))
+}
+
+macro_rules! integer_parse {
+    ($($t:ty),*) => {$(
+        impl Parse for $t {
+            fn parser()->Parser<char, $t, usize, usize> {
+                number(integer())
+            }
+        }
+    )*}
+}
+
+macro_rules! float_parse {
+    ($($t:ty),*) => {$(
+        impl Parse for $t {
+            fn parser()->Parser<char, $t, usize, usize> {
+                number(either(try(float()), integer()))
+            }
+        }
+    )*}
+}
+
+integer_parse!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+float_parse!(f32, f64);
+
+impl Parse for bool {
+    fn parser()->Parser<char, bool, usize, usize> {
+        blank().then(either(try(string("true")).then(pack(true)), string("false").then(pack(false))))
+    }
+}
+
+impl Parse for char {
+    fn parser()->Parser<char, char, usize, usize> {
+        blank().then(one())
+    }
+}
+
+// A double quoted string with backslash escapes, or else a bare word of letters, digits and _.
+impl Parse for String {
+    fn parser()->Parser<char, String, usize, usize> {
+        let escape = eq('\\').then(one_of("\"\\nt")).bind(abc!(|c:char, _: &mut State<char, Index=usize, Tran=usize>|->Status<char, usize>{
+            Ok(match c {
+                'n' => '\n',
+                't' => '\t',
+                c => c,
+            })
+        }));
+        let quoted = between(try(eq('"')), eq('"'), many(either(try(none_of(CharSet::chars("\"\\"))), escape)));
+        let word = many1(satisfy(|c:&char| c.is_alphanumeric() || *c == '_'));
+        blank().then(either(quoted, word)).bind(abc!(|chars:Vec<char>, _: &mut State<char, Index=usize, Tran=usize>|->Status<String, usize>{
+            Ok(chars.into_iter().collect())
+        }))
+    }
+}
+
+impl<T:Parse> Parse for Box<T> {
+    fn parser()->Parser<char, Box<T>, usize, usize> {
+        field::<T>().bind(abc!(|x:T, _: &mut State<char, Index=usize, Tran=usize>|->Status<Box<T>, usize>{
+            Ok(Box::new(x))
+        }))
+    }
+}
+
+impl<T:Parse> Parse for Option<T> {
+    fn parser()->Parser<char, Option<T>, usize, usize> {
+        let p = field::<T>();
+        abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<Option<T>, usize>{
+            let tran = state.begin();
+            match p.parse(state) {
+                Ok(x) => {
+                    state.commit(tran);
+                    Ok(Some(x))
+                }
+                Err(_) => {
+                    state.rollback(tran);
+                    Ok(None)
+                }
+            }
+        })
+    }
+}
+
+impl<T:Parse> Parse for Vec<T> {
+    fn parser()->Parser<char, Vec<T>, usize, usize> {
+        many(field::<T>())
+    }
+}
+
+impl<T:Parse> Repeat for Vec<T> {
+    fn separated(sep:&str)->Parser<char, Vec<T>, usize, usize> {
+        sep_by(field::<T>(), literal(sep))
+    }
+}
@@ -0,0 +1,252 @@
+use parsec::charset::{CharSet, Set};
+use parsec::check::{Rng, Arbitrary};
+use std::collections::HashMap;
+use std::usize;
+
+// Random valid input from a grammar, for fuzzing whatever consumes the language. The
+// description mirrors the combinators, or comes from a PEG Grammar through generator():
+//   Generator::new(vec![("list", between(literal("["), literal("]"), sep_by(rule("item"), literal(",")))),
+//                       ("item", either(class(CharSet::range('0'..='9')), rule("list")))], Config::new())
+// Choices are random and repetitions bounded. Past max_depth nested rules every choice takes
+// an alternative that ends soonest, so the output is always finite.
+#[derive(Debug, Clone)]
+pub enum Gen {
+    Literal(String),
+    Class(CharSet),
+    Any,
+    Rule(String),
+    Seq(Vec<Gen>),
+    Choice(Vec<Gen>),
+    // At least min and at most max times, max None for no bound but max_repeat.
+    Repeat(Box<Gen>, usize, Option<usize>),
+    // Lookahead and other zero width parts, nothing to generate.
+    Empty,
+}
+
+pub fn literal(text:&str)->Gen {
+    Gen::Literal(String::from(text))
+}
+
+pub fn class(set:CharSet)->Gen {
+    Gen::Class(set)
+}
+
+pub fn any()->Gen {
+    Gen::Any
+}
+
+pub fn rule(name:&str)->Gen {
+    Gen::Rule(String::from(name))
+}
+
+pub fn seq(items:Vec<Gen>)->Gen {
+    Gen::Seq(items)
+}
+
+pub fn either(x:Gen, y:Gen)->Gen {
+    Gen::Choice(vec![x, y])
+}
+
+pub fn choice(items:Vec<Gen>)->Gen {
+    Gen::Choice(items)
+}
+
+pub fn many(x:Gen)->Gen {
+    Gen::Repeat(Box::new(x), 0, None)
+}
+
+pub fn many1(x:Gen)->Gen {
+    Gen::Repeat(Box::new(x), 1, None)
+}
+
+pub fn optional(x:Gen)->Gen {
+    Gen::Repeat(Box::new(x), 0, Some(1))
+}
+
+pub fn between(open:Gen, close:Gen, x:Gen)->Gen {
+    Gen::Seq(vec![open, x, close])
+}
+
+pub fn sep_by(x:Gen, sep:Gen)->Gen {
+    optional(sep_by1(x, sep))
+}
+
+pub fn sep_by1(x:Gen, sep:Gen)->Gen {
+    Gen::Seq(vec![x.clone(), many(Gen::Seq(vec![sep, x]))])
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    // Nesting of rules before the generator heads for the shortest way out.
+    pub max_depth: usize,
+    // Most repetitions of an unbounded many or many1 above its minimum.
+    pub max_repeat: usize,
+}
+
+impl Config {
+    pub fn new()->Config {
+        Config{max_depth:8, max_repeat:4}
+    }
+}
+
+const INFINITE:usize = usize::MAX;
+
+pub struct Generator {
+    rules: HashMap<String, Gen>,
+    start: String,
+    // Fewest nested rules each rule needs to finish.
+    costs: HashMap<String, usize>,
+    config: Config,
+}
+
+impl Generator {
+    // The first rule is the start rule. Fails on undefined rules, on repeats whose max is below
+    // their min and on rules that can not produce a finite text, such as a <- 'x' a.
+    pub fn new(rules:Vec<(&str, Gen)>, config:Config)->Result<Generator, String> {
+        let start = match rules.first() {
+            Some(&(name, _)) => String::from(name),
+            None => return Err(String::from("expect at least one rule")),
+        };
+        let mut map = HashMap::new();
+        for (name, gen) in rules {
+            if map.insert(String::from(name), gen).is_some() {
+                return Err(format!("rule {} defined twice", name));
+            }
+        }
+        for gen in map.values() {
+            if let Some(name) = undefined(gen, &map) {
+                return Err(format!("undefined rule {}", name));
+            }
+            if let Some(set) = empty_class(gen) {
+                return Err(format!("class {} has no member", set));
+            }
+            if let Some((min, max)) = bad_repeat(gen) {
+                return Err(format!("repeat max {} is below min {}", max, min));
+            }
+        }
+        let mut costs:HashMap<String, usize> = map.keys().map(|name| (name.clone(), INFINITE)).collect();
+        loop {
+            let mut changed = false;
+            for (name, gen) in &map {
+                let c = cost(gen, &costs);
+                if c < costs[name] {
+                    costs.insert(name.clone(), c);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        if let Some((name, _)) = costs.iter().find(|&(_, c)| *c == INFINITE) {
+            return Err(format!("rule {} can not produce a finite text", name));
+        }
+        Ok(Generator{rules:map, start:start, costs:costs, config:config})
+    }
+
+    pub fn generate(&self, rng:&mut Rng)->String {
+        let start = self.start.clone();
+        self.generate_rule(&start, rng).unwrap()
+    }
+
+    pub fn generate_rule(&self, name:&str, rng:&mut Rng)->Option<String> {
+        let gen = match self.rules.get(name) {
+            Some(gen) => gen,
+            None => return None,
+        };
+        let mut re = String::new();
+        self.run(gen, 1, rng, &mut re);
+        Some(re)
+    }
+
+    fn run(&self, gen:&Gen, depth:usize, rng:&mut Rng, out:&mut String) {
+        let finishing = depth > self.config.max_depth;
+        match *gen {
+            Gen::Literal(ref text) => out.push_str(text),
+            Gen::Class(ref set) => out.push(member(set, rng).unwrap()),
+            Gen::Any => out.push(char::arbitrary(rng, 0)),
+            Gen::Rule(ref name) => self.run(&self.rules[name], depth + 1, rng, out),
+            Gen::Seq(ref items) => {
+                for item in items {
+                    self.run(item, depth, rng, out);
+                }
+            }
+            Gen::Choice(ref items) => {
+                let costs = items.iter().map(|x| cost(x, &self.costs)).collect::<Vec<usize>>();
+                // Alternatives that fit in the depth left, or else the ones ending soonest.
+                let left = (self.config.max_depth + 1).saturating_sub(depth);
+                let fit = (0..items.len()).filter(|i| costs[*i] <= left).collect::<Vec<usize>>();
+                let candidates = if fit.is_empty() {
+                    let best = *costs.iter().min().unwrap();
+                    (0..items.len()).filter(|i| costs[*i] == best).collect()
+                } else {
+                    fit
+                };
+                self.run(&items[*rng.pick(&candidates)], depth, rng, out);
+            }
+            Gen::Repeat(ref x, min, max) => {
+                let count = if finishing {
+                    min
+                } else {
+                    let max = max.unwrap_or(min + self.config.max_repeat);
+                    min + rng.below(max - min + 1)
+                };
+                for _ in 0..count {
+                    self.run(x, depth, rng, out);
+                }
+            }
+            Gen::Empty => {}
+        }
+    }
+}
+
+fn undefined<'a>(gen:&'a Gen, rules:&HashMap<String, Gen>)->Option<&'a str> {
+    match *gen {
+        Gen::Rule(ref name) if !rules.contains_key(name) => Some(name),
+        Gen::Seq(ref items) | Gen::Choice(ref items) => items.iter().filter_map(|x| undefined(x, rules)).next(),
+        Gen::Repeat(ref x, _, _) => undefined(x, rules),
+        _ => None,
+    }
+}
+
+fn empty_class(gen:&Gen)->Option<&CharSet> {
+    match *gen {
+        Gen::Class(ref set) if member(set, &mut Rng::new(0)).is_none() => Some(set),
+        Gen::Seq(ref items) | Gen::Choice(ref items) => items.iter().filter_map(empty_class).next(),
+        Gen::Repeat(ref x, _, _) => empty_class(x),
+        _ => None,
+    }
+}
+
+fn bad_repeat(gen:&Gen)->Option<(usize, usize)> {
+    match *gen {
+        Gen::Repeat(_, min, Some(max)) if max < min => Some((min, max)),
+        Gen::Seq(ref items) | Gen::Choice(ref items) => items.iter().filter_map(bad_repeat).next(),
+        Gen::Repeat(ref x, _, _) => bad_repeat(x),
+        _ => None,
+    }
+}
+
+fn cost(gen:&Gen, costs:&HashMap<String, usize>)->usize {
+    match *gen {
+        Gen::Rule(ref name) => costs[name].saturating_add(1),
+        Gen::Seq(ref items) => items.iter().map(|x| cost(x, costs)).max().unwrap_or(0),
+        Gen::Choice(ref items) => items.iter().map(|x| cost(x, costs)).min().unwrap_or(INFINITE),
+        Gen::Repeat(ref x, min, _) if min > 0 => cost(x, costs),
+        _ => 0,
+    }
+}
+
+// A random ASCII member if there are any, else the first member after a random code point.
+fn member(set:&CharSet, rng:&mut Rng)->Option<char> {
+    let ascii = (0..128u8).map(|c| c as char).filter(|c| set.contains(c)).collect::<Vec<char>>();
+    let printable = ascii.iter().cloned().filter(|c| !c.is_control() || *c == '\n' || *c == '\t').collect::<Vec<char>>();
+    if !printable.is_empty() {
+        return Some(*rng.pick(&printable));
+    }
+    if !ascii.is_empty() {
+        return Some(*rng.pick(&ascii));
+    }
+    let from = 128 + rng.below(0x110000 - 128) as u32;
+    (from..0x110000).chain(128..from).filter_map(::std::char::from_u32).find(|c| set.contains(c))
+}
@@ -0,0 +1,98 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+// Every memoized rule gets its own id when it is built.
+pub fn next_id()->usize {
+    NEXT_ID.fetch_add(1, Ordering::SeqCst)
+}
+
+pub struct Entry<Index> {
+    pub end: Index,
+    pub value: Box<Any>,
+}
+
+// A left recursive rule growing its seed at pos, with the rules that recursion runs through.
+struct Head<Index> {
+    id: usize,
+    pos: Index,
+    involved: HashSet<usize>,
+}
+
+// Packrat table keyed by (rule id, start position). An entry only depends on the input from
+// its start position, so seek_to and rollback move over the table without making it stale.
+pub struct Memo<Index> {
+    table: HashMap<(usize, Index), Entry<Index>>,
+    // Rules running right now, outermost first.
+    calls: Vec<(usize, Index)>,
+    // Left recursive rules growing a seed, outermost first.
+    heads: Vec<Head<Index>>,
+}
+
+impl<Index:Hash+Eq> Memo<Index> {
+    pub fn new()->Memo<Index> {
+        Memo{table:HashMap::new(), calls:Vec::new(), heads:Vec::new()}
+    }
+
+    pub fn get(&self, id:usize, pos:Index)->Option<&Entry<Index>> {
+        self.table.get(&(id, pos))
+    }
+
+    pub fn insert(&mut self, id:usize, pos:Index, entry:Entry<Index>) {
+        self.table.insert((id, pos), entry);
+    }
+
+    pub fn remove(&mut self, id:usize, pos:Index)->Option<Entry<Index>> {
+        self.table.remove(&(id, pos))
+    }
+
+    pub fn len(&self)->usize {
+        self.table.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    pub fn enter(&mut self, id:usize, pos:Index) {
+        self.calls.push((id, pos));
+    }
+
+    pub fn leave(&mut self) {
+        self.calls.pop();
+    }
+
+    pub fn start_growth(&mut self, id:usize, pos:Index) {
+        self.heads.push(Head{id:id, pos:pos, involved:HashSet::new()});
+    }
+
+    pub fn stop_growth(&mut self) {
+        self.heads.pop();
+    }
+
+    pub fn is_growing(&self, id:usize, pos:&Index)->bool {
+        self.heads.iter().any(|head| head.id == id && head.pos == *pos)
+    }
+
+    // A call at pos got the seed of the rule id growing there: every rule running between that
+    // rule's own call and this one is part of the recursion (Warth's involved set).
+    pub fn involve(&mut self, id:usize, pos:&Index) {
+        let start = match self.calls.iter().rposition(|call| call.0 == id && call.1 == *pos) {
+            Some(start) => start,
+            None => return,
+        };
+        let calls = &self.calls[start + 1..];
+        if let Some(head) = self.heads.iter_mut().rev().find(|head| head.id == id && head.pos == *pos) {
+            head.involved.extend(calls.iter().filter(|call| call.1 == *pos).map(|call| call.0));
+        }
+    }
+
+    // Whether rule id at pos runs through a recursion still growing there. Its result may rest
+    // on a stale seed then, so it must not be recorded; every other rule records as usual.
+    pub fn is_involved(&self, id:usize, pos:&Index)->bool {
+        self.heads.iter().any(|head| head.id != id && head.pos == *pos && head.involved.contains(&id))
+    }
+}
@@ -7,14 +7,23 @@ use std::fmt;
 use std::clone::Clone;
 use std::convert::{From};
 use std::error;
-use std::marker::Reflect;
+use parsec::memo::Memo;
+use parsec::trace::Tracer;
+use parsec::profile::Profiler;
+use parsec::atom::eof;
 
 pub trait State<T> {
-    type Index:Reflect+Debug;
+    type Index:Debug;
     type Tran;
     fn pos(&self)-> Self::Index;
+    // Where the last element read ends, which closes a span. For element indexed states this
+    // is pos itself.
+    fn end_pos(&self)->Self::Index {
+        self.pos()
+    }
     fn seek_to(&mut self, Self::Index)->bool;
     fn next(&mut self)->Option<T>;
+    // Reads the next element if pred accepts it, otherwise leaves the position where it was.
     fn next_by(&mut self, &Fn(&T)->bool)->Status<T, Self::Index>;
     fn err(&self, description:String)->ParsecError<Self::Index> {
         ParsecError::new(self.pos(), description)
@@ -22,12 +31,22 @@ pub trait State<T> {
     fn begin(&mut self)->Self::Tran;
     fn commit(&mut self, Self::Tran);
     fn rollback(&mut self, Self::Tran);
+    fn memo(&mut self)->Option<&mut Memo<Self::Index>> {
+        None
+    }
+    fn tracer(&mut self)->Option<&mut Tracer<Self::Index>> {
+        None
+    }
+    fn profiler(&mut self)->Option<&mut Profiler> {
+        None
+    }
 }
 
 pub struct VecState<T> {
     index : usize,
     tran : Option<usize>,
     buffer: Vec<T>,
+    memo: Memo<usize>,
 }
 
 impl<A> FromIterator<A> for VecState<A> {
@@ -36,10 +55,17 @@ impl<A> FromIterator<A> for VecState<A> {
             index:0,
             tran:None,
             buffer:iterator.into_iter().collect(),
+            memo:Memo::new(),
         }
     }
 }
 
+impl<T> VecState<T> {
+    pub fn rest(&self)->&[T] {
+        &self.buffer[self.index..]
+    }
+}
+
 impl<T> State<T> for VecState<T> where T:Clone {
     type Index = usize;
     type Tran = usize;
@@ -47,7 +73,7 @@ impl<T> State<T> for VecState<T> where T:Clone {
         self.index
     }
     fn seek_to(&mut self, to:usize) -> bool {
-        if to < self.buffer.len() {
+        if to <= self.buffer.len() {
             self.index = to;
             true
         } else {
@@ -66,8 +92,8 @@ impl<T> State<T> for VecState<T> where T:Clone {
     fn next_by(&mut self, pred:&Fn(&T)->bool)->Status<T, usize>{
         if self.index < self.buffer.len() {
             let item = &self.buffer[self.index];
-            self.index += 1;
             if pred(item) {
+                self.index += 1;
                 Ok(item.clone())
             } else {
                 Err(self.err(String::from("predicate failed")))
@@ -95,6 +121,10 @@ impl<T> State<T> for VecState<T> where T:Clone {
             self.tran = None;
         }
     }
+
+    fn memo(&mut self)->Option<&mut Memo<usize>> {
+        Some(&mut self.memo)
+    }
 }
 
 pub trait Error: error::Error {
@@ -103,12 +133,12 @@ pub trait Error: error::Error {
 }
 
 #[derive(Debug, Clone)]
-pub struct ParsecError<Index:Debug+Reflect+'static> {
+pub struct ParsecError<Index:Debug+'static> {
     _pos: Index,
     message: String,
 }
 
-impl<Index:Debug+Reflect+'static> ParsecError<Index> {
+impl<Index:Debug+'static> ParsecError<Index> {
     pub fn new(pos:Index, description:String)->ParsecError<Index>{
         ParsecError{
             _pos: pos,
@@ -117,14 +147,14 @@ impl<Index:Debug+Reflect+'static> ParsecError<Index> {
     }
 }
 
-impl<Index:Debug+Reflect+Clone+'static> Error for ParsecError<Index> {
+impl<Index:Debug+Clone+'static> Error for ParsecError<Index> {
     type Index = Index;
     fn pos(&self)->Index {
         self._pos.clone()
     }
 }
 
-impl<Index:Debug+Reflect+'static> error::Error for ParsecError<Index> {
+impl<Index:Debug+'static> error::Error for ParsecError<Index> {
     fn description(&self)->&str {
         self.message.as_str()
     }
@@ -133,7 +163,7 @@ impl<Index:Debug+Reflect+'static> error::Error for ParsecError<Index> {
     }
 }
 
-impl<Index:Reflect+Debug> Display for ParsecError<Index> {
+impl<Index:Debug> Display for ParsecError<Index> {
     fn fmt(&self, formatter:&mut Formatter) -> Result<(), fmt::Error> {
         write!(formatter, "{}", self.message)
     }
@@ -141,7 +171,7 @@ impl<Index:Reflect+Debug> Display for ParsecError<Index> {
 
 //pub trait Parsec<T:'static+Clone, R:'static+Clone>:Debug where Self:Parsec<T, R, Tran:'static>+Clone+'static {
 pub trait Parsec<T, R> {
-    type Index:Reflect+Debug;
+    type Index:Debug;
     type Tran;
     fn parse(&self, &mut State<T, Index=Self::Index, Tran=Self::Tran>)->Status<R, Self::Index>;
 }
@@ -192,7 +222,7 @@ pub type Status<T, Index> = Result<T, ParsecError<Index>>;
 
 pub type Parser<T, R, Index, Tran> = Arc<Box<Fn(&mut State<T, Index=Index, Tran=Tran>)->Status<R, Index>>>;
 
-impl<T, R, Index:Debug+Reflect+'static, Tran:'static> Parsec<T, R> for Parser<T, R, Index, Tran> where T:Clone, R:Clone {
+impl<T, R, Index:Debug+'static, Tran:'static> Parsec<T, R> for Parser<T, R, Index, Tran> where T:Clone, R:Clone {
     type Index=Index;
     type Tran=Tran;
     fn parse(&self, state: &mut State<T, Index=Index, Tran=Tran>) -> Status<R, Index> {
@@ -200,8 +230,135 @@ impl<T, R, Index:Debug+Reflect+'static, Tran:'static> Parsec<T, R> for Parser<T,
     }
 }
 
-impl<T:'static, R:'static, Index:Debug+Reflect+'static, Tran:'static> Monad<T, R> for Parser<T, R, Index, Tran> where T:Clone, R:Clone {}
+impl<T:'static, R:'static, Index:Debug+'static, Tran:'static> Monad<T, R> for Parser<T, R, Index, Tran> where T:Clone, R:Clone {}
+
+// The inputs parse takes. Marker tells the impl for any iterator apart from the ones for
+// collections and strings, which std might make iterators some day; callers leave it inferred.
+pub trait IntoState<T, Marker=()> {
+    fn into_state(self)->VecState<T>;
+}
+
+pub struct FromIter;
+
+impl<T, I> IntoState<T, FromIter> for I where I:Iterator<Item=T> {
+    fn into_state(self)->VecState<T> {
+        VecState::from_iter(self)
+    }
+}
+
+impl<T> IntoState<T> for VecState<T> {
+    fn into_state(self)->VecState<T> {
+        self
+    }
+}
+
+impl<T> IntoState<T> for Vec<T> {
+    fn into_state(self)->VecState<T> {
+        VecState::from_iter(self)
+    }
+}
+
+impl<'a, T:Clone> IntoState<T> for &'a [T] {
+    fn into_state(self)->VecState<T> {
+        VecState::from_iter(self.iter().cloned())
+    }
+}
+
+impl<'a, T:Clone> IntoState<T> for &'a Vec<T> {
+    fn into_state(self)->VecState<T> {
+        VecState::from_iter(self.iter().cloned())
+    }
+}
+
+impl<'a> IntoState<char> for &'a str {
+    fn into_state(self)->VecState<char> {
+        VecState::from_iter(self.chars())
+    }
+}
+
+impl<'a> IntoState<char> for &'a String {
+    fn into_state(self)->VecState<char> {
+        VecState::from_iter(self.chars())
+    }
+}
+
+impl IntoState<char> for String {
+    fn into_state(self)->VecState<char> {
+        VecState::from_iter(self.chars())
+    }
+}
+
+// What a top level parse leaves behind: value or error, where it stopped and what is left.
+#[derive(Debug, Clone)]
+pub struct Parsed<T, R> {
+    pub value: Option<R>,
+    pub pos: usize,
+    pub rest: Vec<T>,
+    pub error: Option<ParsecError<usize>>,
+}
+
+impl<T, R> Parsed<T, R> {
+    pub fn is_ok(&self)->bool {
+        self.error.is_none()
+    }
+
+    pub fn into_result(self)->Status<R, usize> {
+        match self.value {
+            Some(value) => Ok(value),
+            None => Err(self.error.unwrap()),
+        }
+    }
+}
+
+pub fn parse<T, R, X, I, M>(p:X, input:I)->Parsed<T, R>
+where T:Clone, X:Parsec<T, R, Index=usize, Tran=usize>, I:IntoState<T, M> {
+    let mut state = input.into_state();
+    let re = p.parse(&mut state);
+    parsed(state, re)
+}
+
+// Like parse, but the whole input must be consumed.
+pub fn parse_complete<T, R, X, I, M>(p:X, input:I)->Parsed<T, R>
+where T:Clone+Display+'static, X:Parsec<T, R, Index=usize, Tran=usize>, I:IntoState<T, M> {
+    let mut state = input.into_state();
+    let re = p.parse(&mut state).and_then(|value| {
+        let end = state.pos();
+        match eof().parse(&mut state) {
+            Ok(_) => Ok(value),
+            Err(err) => {
+                state.seek_to(end);
+                Err(err)
+            }
+        }
+    });
+    parsed(state, re)
+}
+
+fn parsed<T:Clone, R>(state:VecState<T>, re:Status<R, usize>)->Parsed<T, R> {
+    let (value, error) = match re {
+        Ok(value) => (Some(value), None),
+        Err(err) => (None, Some(err)),
+    };
+    Parsed{
+        value: value,
+        pos: state.pos(),
+        rest: state.rest().to_vec(),
+        error: error,
+    }
+}
 
 pub mod atom;
+pub mod charset;
+pub mod check;
 pub mod combinator;
-// pub mod text;
+pub mod derive;
+pub mod gen;
+pub mod memo;
+pub mod peg;
+pub mod profile;
+pub mod regex;
+pub mod span;
+pub mod stream;
+pub mod text;
+pub mod token;
+pub mod trace;
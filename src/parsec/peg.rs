@@ -0,0 +1,420 @@
+use parsec::{State, Status, Parser, Parsec, Monad, ParsecError, parse_complete};
+use parsec::atom::{eq, one, one_of, none_of, string, satisfy, take_while, pack};
+use parsec::charset::CharSet;
+use parsec::combinator::{either, try, many, many1, sep_by1, between, skip, fix, look_ahead, not_followed_by, Or};
+use parsec::check::Rng;
+use parsec::gen::{Gen, Generator, Config};
+use parsec::span::{Span, spanned};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+use std::sync::Arc;
+
+// A PEG grammar read from text at runtime and compiled into combinators:
+//
+//   # comments run to the end of the line
+//   sum    <- product (('+' / '-') product)*
+//   number <- [0-9]+ ('.' [0-9]+)?
+//   name   <- !keyword [a-zA-Z_] [a-zA-Z0-9_]*
+//
+// Literals are quoted with ' or ", classes take ranges and a leading ^, . is any char, and
+// e* e+ e? &e !e (e) and e1 / e2 mean what they mean in PEG. The first rule is the start rule,
+// and a rule that can reach itself before consuming input is rejected as left recursive.
+// Every rule that matches yields a Tree, nested by the rules it called.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tree {
+    pub rule: String,
+    pub text: String,
+    pub span: Span,
+    pub children: Vec<Tree>,
+}
+
+impl Tree {
+    // Rule nodes directly below this one with the given name.
+    pub fn children_of<'a>(&'a self, rule:&'a str)->Box<Iterator<Item=&'a Tree>+'a> {
+        Box::new(self.children.iter().filter(move |t| t.rule == rule))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Literal(String),
+    Class(CharSet),
+    Any,
+    Rule(String, usize),
+    Seq(Vec<Expr>),
+    Choice(Vec<Expr>),
+    Star(Box<Expr>),
+    Plus(Box<Expr>),
+    Optional(Box<Expr>),
+    And(Box<Expr>),
+    Not(Box<Expr>),
+}
+
+type Rules = RefCell<HashMap<String, Parser<char, Tree, usize, usize>>>;
+type Piece = (String, Vec<Tree>);
+
+// Compiled rules refer to each other weakly, so they work as long as the Grammar lives.
+pub struct Grammar {
+    rules: Rc<Rules>,
+    names: Vec<String>,
+    exprs: Vec<Expr>,
+}
+
+impl Grammar {
+    pub fn new(text:&str)->Status<Grammar, usize> {
+        let definitions = try!(parse_complete(space().then(many1(definition())), text).into_result());
+        let rules:Rc<Rules> = Rc::new(RefCell::new(HashMap::new()));
+        let mut names = Vec::new();
+        for &(ref name, pos, _) in &definitions {
+            if names.contains(name) {
+                return Err(ParsecError::new(pos, format!("rule {} defined twice", name)));
+            }
+            names.push(name.clone());
+        }
+        for &(_, _, ref expr) in &definitions {
+            try!(check(expr, &names));
+        }
+        let exprs = definitions.iter().map(|&(_, _, ref expr)| expr).collect::<Vec<_>>();
+        if let Some(at) = left_recursive(&exprs, &names) {
+            return Err(ParsecError::new(definitions[at].1, format!("rule {} is left recursive", names[at])));
+        }
+        let mut exprs = Vec::new();
+        for (name, _, expr) in definitions {
+            let p = rule(name.clone(), compile(&expr, Rc::downgrade(&rules)));
+            rules.borrow_mut().insert(name, p);
+            exprs.push(expr);
+        }
+        Ok(Grammar{rules:rules, names:names, exprs:exprs})
+    }
+
+    pub fn names(&self)->&[String] {
+        &self.names
+    }
+
+    pub fn rule(&self, name:&str)->Option<Parser<char, Tree, usize, usize>> {
+        self.rules.borrow().get(name).cloned()
+    }
+
+    pub fn parser(&self)->Parser<char, Tree, usize, usize> {
+        self.rule(&self.names[0]).unwrap()
+    }
+
+    // Runs the start rule over the whole text.
+    pub fn parse(&self, text:&str)->Status<Tree, usize> {
+        parse_complete(self.parser(), text).into_result()
+    }
+
+    // Random texts of the start rule. & and ! generate nothing, so in a grammar with
+    // lookaheads a text may still be rejected; generate filters those.
+    pub fn generator(&self, config:Config)->Result<Generator, String> {
+        let rules = self.names.iter().zip(&self.exprs).map(|(name, x)| (name.as_str(), to_gen(x))).collect();
+        Generator::new(rules, config)
+    }
+
+    // The first of up to tries generated texts that the grammar accepts.
+    pub fn generate(&self, rng:&mut Rng, config:Config, tries:usize)->Result<String, String> {
+        let generator = try!(self.generator(config));
+        for _ in 0..tries {
+            let text = generator.generate(rng);
+            if self.parse(&text).is_ok() {
+                return Ok(text);
+            }
+        }
+        Err(format!("no valid text in {} tries", tries))
+    }
+}
+
+fn to_gen(expr:&Expr)->Gen {
+    match *expr {
+        Expr::Literal(ref text) => Gen::Literal(text.clone()),
+        Expr::Class(ref set) => Gen::Class(set.clone()),
+        Expr::Any => Gen::Any,
+        Expr::Rule(ref name, _) => Gen::Rule(name.clone()),
+        Expr::Seq(ref items) => Gen::Seq(items.iter().map(to_gen).collect()),
+        Expr::Choice(ref items) => Gen::Choice(items.iter().map(to_gen).collect()),
+        Expr::Star(ref x) => Gen::Repeat(Box::new(to_gen(x)), 0, None),
+        Expr::Plus(ref x) => Gen::Repeat(Box::new(to_gen(x)), 1, None),
+        Expr::Optional(ref x) => Gen::Repeat(Box::new(to_gen(x)), 0, Some(1)),
+        Expr::And(_) | Expr::Not(_) => Gen::Empty,
+    }
+}
+
+fn check(expr:&Expr, names:&[String])->Status<(), usize> {
+    match *expr {
+        Expr::Rule(ref name, pos) => {
+            if names.contains(name) {
+                Ok(())
+            } else {
+                Err(ParsecError::new(pos, format!("undefined rule {}", name)))
+            }
+        }
+        Expr::Seq(ref items) | Expr::Choice(ref items) => {
+            for item in items {
+                try!(check(item, names));
+            }
+            Ok(())
+        }
+        Expr::Star(ref x) | Expr::Plus(ref x) | Expr::Optional(ref x) | Expr::And(ref x) | Expr::Not(ref x) => check(x, names),
+        _ => Ok(()),
+    }
+}
+
+// Whether the expression can match without consuming anything, given the same for each rule.
+fn nullable(expr:&Expr, names:&[String], rules:&[bool])->bool {
+    match *expr {
+        Expr::Literal(ref text) => text.is_empty(),
+        Expr::Class(_) | Expr::Any => false,
+        Expr::Rule(ref name, _) => names.iter().position(|n| n == name).map_or(false, |at| rules[at]),
+        Expr::Seq(ref items) => items.iter().all(|x| nullable(x, names, rules)),
+        Expr::Choice(ref items) => items.iter().any(|x| nullable(x, names, rules)),
+        Expr::Plus(ref x) => nullable(x, names, rules),
+        Expr::Star(_) | Expr::Optional(_) | Expr::And(_) | Expr::Not(_) => true,
+    }
+}
+
+// Rules the expression may call before it has consumed anything.
+fn leftmost(expr:&Expr, names:&[String], rules:&[bool], re:&mut Vec<usize>) {
+    match *expr {
+        Expr::Rule(ref name, _) => re.extend(names.iter().position(|n| n == name)),
+        Expr::Seq(ref items) => {
+            for x in items {
+                leftmost(x, names, rules, re);
+                if !nullable(x, names, rules) {
+                    break;
+                }
+            }
+        }
+        Expr::Choice(ref items) => {
+            for x in items {
+                leftmost(x, names, rules, re);
+            }
+        }
+        Expr::Star(ref x) | Expr::Plus(ref x) | Expr::Optional(ref x) | Expr::And(ref x) | Expr::Not(ref x) => {
+            leftmost(x, names, rules, re)
+        }
+        _ => {}
+    }
+}
+
+// The first rule that can call itself again without consuming anything, which would recurse
+// until the stack overflows.
+fn left_recursive(exprs:&[&Expr], names:&[String])->Option<usize> {
+    let mut rules = vec![false; exprs.len()];
+    loop {
+        let next = exprs.iter().map(|x| nullable(x, names, &rules)).collect::<Vec<bool>>();
+        if next == rules {
+            break;
+        }
+        rules = next;
+    }
+    let calls = exprs.iter().map(|x| {
+        let mut re = Vec::new();
+        leftmost(x, names, &rules, &mut re);
+        re
+    }).collect::<Vec<_>>();
+    (0..exprs.len()).find(|&start| {
+        let mut seen = vec![false; exprs.len()];
+        let mut todo = calls[start].clone();
+        while let Some(at) = todo.pop() {
+            if at == start {
+                return true;
+            }
+            if !seen[at] {
+                seen[at] = true;
+                todo.extend(calls[at].iter().cloned());
+            }
+        }
+        false
+    })
+}
+
+fn rule(name:String, body:Parser<char, Piece, usize, usize>)->Parser<char, Tree, usize, usize> {
+    let body = spanned(body);
+    abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<Tree, usize>{
+        body.parse(state).map(|((text, children), span)| Tree{rule:name.clone(), text:text, span:span, children:children})
+    })
+}
+
+fn compile(expr:&Expr, rules:Weak<Rules>)->Parser<char, Piece, usize, usize> {
+    match *expr {
+        Expr::Literal(ref text) => {
+            let text = text.clone();
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (None, _) => pack((String::new(), Vec::new())),
+                (Some(c), None) => piece(try(eq(c)).then(pack(text))),
+                _ => piece(string(&text)),
+            }
+        }
+        Expr::Class(ref set) => piece(chars(try(one_of(set.clone())))),
+        Expr::Any => piece(chars(one())),
+        Expr::Rule(ref name, _) => {
+            let name = name.clone();
+            abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<Piece, usize>{
+                let p = rules.upgrade().and_then(|rules| rules.borrow().get(&name).cloned());
+                match p {
+                    Some(p) => p.parse(state).map(|tree| (tree.text.clone(), vec![tree])),
+                    None => Err(state.err(String::from("grammar has been dropped"))),
+                }
+            })
+        }
+        Expr::Seq(ref items) => {
+            let items = items.iter().map(|x| compile(x, rules.clone())).collect::<Vec<_>>();
+            abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<Piece, usize>{
+                let mut re = (String::new(), Vec::new());
+                for item in &items {
+                    let (text, trees) = try!(item.parse(state));
+                    re.0.push_str(&text);
+                    re.1.extend(trees);
+                }
+                Ok(re)
+            })
+        }
+        Expr::Choice(ref items) => {
+            let mut items = items.iter().rev().map(|x| compile(x, rules.clone()));
+            let last = items.next().unwrap();
+            items.fold(last, |re, item| either(try(item), re))
+        }
+        Expr::Star(ref x) => concat(many(compile(x, rules))),
+        Expr::Plus(ref x) => concat(many1(try(compile(x, rules)))),
+        Expr::Optional(ref x) => either(try(compile(x, rules)), pack((String::new(), Vec::new()))),
+        Expr::And(ref x) => look_ahead(compile(x, rules)).then(pack((String::new(), Vec::new()))),
+        Expr::Not(ref x) => not_followed_by(compile(x, rules)).then(pack((String::new(), Vec::new()))),
+    }
+}
+
+fn chars(p:Parser<char, char, usize, usize>)->Parser<char, String, usize, usize> {
+    p.bind(abc!(|c:char, _: &mut State<char, Index=usize, Tran=usize>|->Status<String, usize>{
+        Ok(c.to_string())
+    }))
+}
+
+fn piece(p:Parser<char, String, usize, usize>)->Parser<char, Piece, usize, usize> {
+    p.bind(abc!(|text:String, _: &mut State<char, Index=usize, Tran=usize>|->Status<Piece, usize>{
+        Ok((text, Vec::new()))
+    }))
+}
+
+fn concat(p:Parser<char, Vec<Piece>, usize, usize>)->Parser<char, Piece, usize, usize> {
+    p.bind(abc!(|pieces:Vec<Piece>, _: &mut State<char, Index=usize, Tran=usize>|->Status<Piece, usize>{
+        Ok(pieces.into_iter().fold((String::new(), Vec::new()), |mut re, (text, trees)| {
+            re.0.push_str(&text);
+            re.1.extend(trees);
+            re
+        }))
+    }))
+}
+
+// The grammar of grammars.
+
+fn space()->Parser<char, Vec<char>, usize, usize> {
+    let comment = eq('#').then(skip(none_of(CharSet::chars("\n")))).then(pack(' '));
+    skip(either(try(one_of(" \t\r\n")), comment))
+}
+
+fn token(text:&str)->Parser<char, String, usize, usize> {
+    string(text).over(space())
+}
+
+fn name()->Parser<char, (String, Span), usize, usize> {
+    let first = satisfy(|c:&char| c.is_alphabetic() || *c == '_');
+    let rest = take_while(|c:&char| c.is_alphanumeric() || *c == '_');
+    spanned(first.bind(abc!(move |c:char, state: &mut State<char, Index=usize, Tran=usize>|->Status<String, usize>{
+        let mut re = c.to_string();
+        re.extend(try!(rest.parse(state)));
+        Ok(re)
+    }))).over(space())
+}
+
+fn escaped(quote:Option<char>)->Parser<char, char, usize, usize> {
+    let plain = match quote {
+        Some(q) => none_of(vec![q, '\\']),
+        None => none_of(CharSet::chars("]\\")),
+    };
+    let escape = eq('\\').then(one()).bind(abc!(|c:char, _: &mut State<char, Index=usize, Tran=usize>|->Status<char, usize>{
+        Ok(match c {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            c => c,
+        })
+    }));
+    either(try(plain), escape)
+}
+
+fn literal()->Parser<char, Expr, usize, usize> {
+    let quoted = |q:char| between(try(eq(q)), eq(q), many(escaped(Some(q))));
+    either(quoted('\''), quoted('"')).over(space()).bind(abc!(|chars:Vec<char>, _: &mut State<char, Index=usize, Tran=usize>|->Status<Expr, usize>{
+        Ok(Expr::Literal(chars.into_iter().collect()))
+    }))
+}
+
+fn class()->Parser<char, Expr, usize, usize> {
+    let item = escaped(None).bind(abc!(|lo:char, state: &mut State<char, Index=usize, Tran=usize>|->Status<CharSet, usize>{
+        let tran = state.begin();
+        let hi = eq('-').then(escaped(None)).parse(state);
+        match hi {
+            Ok(hi) => {
+                state.commit(tran);
+                Ok(CharSet::range(lo..=hi))
+            }
+            Err(_) => {
+                state.rollback(tran);
+                Ok(CharSet::chars(&lo.to_string()))
+            }
+        }
+    }));
+    let negated = either(try(eq('^')).then(pack(true)), pack(false));
+    try(eq('[')).then(negated).bind(abc!(move |negated:bool, state: &mut State<char, Index=usize, Tran=usize>|->Status<Expr, usize>{
+        let items = try!(many(item.clone()).parse(state));
+        try!(eq(']').over(space()).parse(state));
+        let set = items.into_iter().fold(CharSet::new(), |set, x| set.union(x));
+        Ok(Expr::Class(if negated { set.negate() } else { set }))
+    }))
+}
+
+fn expression()->Parser<char, Expr, usize, usize> {
+    fix(|expression| {
+        let reference = try(name().over(not_followed_by(string("<-")))).bind(abc!(|(name, span):(String, Span), _: &mut State<char, Index=usize, Tran=usize>|->Status<Expr, usize>{
+            Ok(Expr::Rule(name, span.start))
+        }));
+        let group = between(try(token("(")), token(")"), expression);
+        let any = try(token(".")).then(pack(Expr::Any));
+        let primary = either(reference, group).or(literal()).or(class()).or(any);
+        let suffixed = primary.bind(abc!(|x:Expr, state: &mut State<char, Index=usize, Tran=usize>|->Status<Expr, usize>{
+            let suffix = either(try(one_of("*+?")).over(space()).bind(abc!(|c:char, _: &mut State<char, Index=usize, Tran=usize>|->Status<Option<char>, usize>{
+                Ok(Some(c))
+            })), pack(None));
+            Ok(match try!(suffix.parse(state)) {
+                Some('*') => Expr::Star(Box::new(x.clone())),
+                Some('+') => Expr::Plus(Box::new(x.clone())),
+                Some(_) => Expr::Optional(Box::new(x.clone())),
+                None => x.clone(),
+            })
+        }));
+        let prefixed = either(try(one_of("&!")).over(space()).bind(abc!(|c:char, _: &mut State<char, Index=usize, Tran=usize>|->Status<Option<char>, usize>{
+            Ok(Some(c))
+        })), pack(None)).bind(abc!(move |prefix:Option<char>, state: &mut State<char, Index=usize, Tran=usize>|->Status<Expr, usize>{
+            let x = try!(suffixed.parse(state));
+            Ok(match prefix {
+                Some('&') => Expr::And(Box::new(x)),
+                Some(_) => Expr::Not(Box::new(x)),
+                None => x,
+            })
+        }));
+        let sequence = many1(try(prefixed)).bind(abc!(|mut items:Vec<Expr>, _: &mut State<char, Index=usize, Tran=usize>|->Status<Expr, usize>{
+            Ok(if items.len() == 1 { items.pop().unwrap() } else { Expr::Seq(items) })
+        }));
+        sep_by1(sequence, token("/")).bind(abc!(|mut items:Vec<Expr>, _: &mut State<char, Index=usize, Tran=usize>|->Status<Expr, usize>{
+            Ok(if items.len() == 1 { items.pop().unwrap() } else { Expr::Choice(items) })
+        }))
+    })
+}
+
+fn definition()->Parser<char, (String, usize, Expr), usize, usize> {
+    let expression = expression();
+    name().over(token("<-")).bind(abc!(move |(name, span):(String, Span), state: &mut State<char, Index=usize, Tran=usize>|->Status<(String, usize, Expr), usize>{
+        expression.parse(state).map(|x| (name.clone(), span.start, x))
+    }))
+}
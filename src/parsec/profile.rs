@@ -0,0 +1,176 @@
+use parsec::{State, Status};
+use parsec::memo::Memo;
+use parsec::trace::Tracer;
+use std::collections::HashMap;
+use std::fmt::{Formatter, Display};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleStats {
+    pub rule: String,
+    pub calls: usize,
+    pub failures: usize,
+    pub backtracks: usize,
+    // Time inside the rule including nested rules, recursive calls counted once.
+    pub total: Duration,
+    // Time inside the rule minus the time of nested traced rules.
+    pub own: Duration,
+}
+
+struct Frame {
+    rule: String,
+    start: Instant,
+    children: Duration,
+}
+
+// Collects per rule statistics from the trace rules running on a ProfileState.
+pub struct Profiler {
+    stats: HashMap<String, RuleStats>,
+    stack: Vec<Frame>,
+}
+
+impl Profiler {
+    pub fn new()->Profiler {
+        Profiler{stats:HashMap::new(), stack:Vec::new()}
+    }
+
+    pub fn enter(&mut self, rule:&str) {
+        self.stat(rule).calls += 1;
+        self.stack.push(Frame{rule:String::from(rule), start:Instant::now(), children:Duration::new(0, 0)});
+    }
+
+    pub fn exit(&mut self, ok:bool) {
+        let frame = match self.stack.pop() {
+            Some(frame) => frame,
+            None => return,
+        };
+        let elapsed = frame.start.elapsed();
+        let recursive = self.stack.iter().any(|f| f.rule == frame.rule);
+        if let Some(parent) = self.stack.last_mut() {
+            parent.children += elapsed;
+        }
+        let stat = self.stat(&frame.rule);
+        if !ok {
+            stat.failures += 1;
+        }
+        if !recursive {
+            stat.total += elapsed;
+        }
+        stat.own += elapsed.checked_sub(frame.children).unwrap_or(Duration::new(0, 0));
+    }
+
+    // A rollback is charged to the innermost rule running when it happens.
+    pub fn backtrack(&mut self) {
+        let rule = match self.stack.last() {
+            Some(frame) => frame.rule.clone(),
+            None => return,
+        };
+        self.stat(&rule).backtracks += 1;
+    }
+
+    pub fn get(&self, rule:&str)->Option<&RuleStats> {
+        self.stats.get(rule)
+    }
+
+    // Sorted by total time, then by backtracks.
+    pub fn report(&self)->Vec<RuleStats> {
+        let mut re = self.stats.values().cloned().collect::<Vec<RuleStats>>();
+        re.sort_by(|x, y| y.total.cmp(&x.total)
+            .then(y.backtracks.cmp(&x.backtracks))
+            .then(x.rule.cmp(&y.rule)));
+        re
+    }
+
+    fn stat(&mut self, rule:&str)->&mut RuleStats {
+        self.stats.entry(String::from(rule)).or_insert_with(|| RuleStats{
+            rule: String::from(rule),
+            calls: 0,
+            failures: 0,
+            backtracks: 0,
+            total: Duration::new(0, 0),
+            own: Duration::new(0, 0),
+        })
+    }
+}
+
+fn millis(d:&Duration)->f64 {
+    d.as_secs() as f64 * 1000.0 + d.subsec_nanos() as f64 / 1000000.0
+}
+
+impl Display for Profiler {
+    fn fmt(&self, formatter:&mut Formatter) -> Result<(), fmt::Error> {
+        let report = self.report();
+        let width = report.iter().map(|s| s.rule.chars().count()).max().unwrap_or(0).max(4);
+        try!(writeln!(formatter, "{:<w$} {:>10} {:>10} {:>10} {:>12} {:>12}",
+            "rule", "calls", "failures", "backtracks", "total(ms)", "own(ms)", w=width));
+        for s in &report {
+            try!(writeln!(formatter, "{:<w$} {:>10} {:>10} {:>10} {:>12.3} {:>12.3}",
+                s.rule, s.calls, s.failures, s.backtracks, millis(&s.total), millis(&s.own), w=width));
+        }
+        Ok(())
+    }
+}
+
+// Wraps any state and profiles the trace rules that run on it.
+pub struct ProfileState<S> {
+    inner: S,
+    profiler: Profiler,
+}
+
+impl<S> ProfileState<S> {
+    pub fn new(inner:S)->ProfileState<S> {
+        ProfileState{inner:inner, profiler:Profiler::new()}
+    }
+
+    pub fn profile(&self)->&Profiler {
+        &self.profiler
+    }
+
+    pub fn into_inner(self)->S {
+        self.inner
+    }
+}
+
+impl<T, S> State<T> for ProfileState<S> where S:State<T>, S::Index:PartialOrd {
+    type Index = S::Index;
+    type Tran = S::Tran;
+    fn pos(&self)->S::Index {
+        self.inner.pos()
+    }
+    fn end_pos(&self)->S::Index {
+        self.inner.end_pos()
+    }
+    fn seek_to(&mut self, to:S::Index)->bool {
+        self.inner.seek_to(to)
+    }
+    fn next(&mut self)->Option<T> {
+        self.inner.next()
+    }
+    fn next_by(&mut self, pred:&Fn(&T)->bool)->Status<T, S::Index> {
+        self.inner.next_by(pred)
+    }
+    fn begin(&mut self)->S::Tran {
+        self.inner.begin()
+    }
+    fn commit(&mut self, tran:S::Tran) {
+        self.inner.commit(tran)
+    }
+    // Only a rollback that moves the position back re-reads input, so only that is a backtrack.
+    fn rollback(&mut self, tran:S::Tran) {
+        let before = self.inner.pos();
+        self.inner.rollback(tran);
+        if self.inner.pos() < before {
+            self.profiler.backtrack();
+        }
+    }
+    fn memo(&mut self)->Option<&mut Memo<S::Index>> {
+        self.inner.memo()
+    }
+    fn tracer(&mut self)->Option<&mut Tracer<S::Index>> {
+        self.inner.tracer()
+    }
+    fn profiler(&mut self)->Option<&mut Profiler> {
+        Some(&mut self.profiler)
+    }
+}
@@ -0,0 +1,355 @@
+use parsec::{State, ParsecError, Status, Parser};
+use parsec::charset::{CharSet, Category, Set};
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+// A small regex engine: the pattern compiles to a Thompson NFA which runs as a Pike VM,
+// so matching is linear in the input and never backtracks through the State.
+// Supported: literals, ., [classes], \d \w \s \D \W \S, (groups), (?:groups), |, * + ? {m} {m,} {m,n}.
+#[derive(Debug, Clone)]
+pub struct Regex {
+    pattern: String,
+    program: Vec<Inst>,
+}
+
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(CharSet),
+    Split(usize, usize),
+    Jmp(usize),
+    Match,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Class(CharSet),
+    Concat(Vec<Node>),
+    Alt(Vec<Node>),
+    Repeat(Box<Node>, usize, Option<usize>),
+}
+
+impl Regex {
+    pub fn new(pattern:&str)->Result<Regex, String> {
+        let mut parser = PatternParser{chars:pattern.chars().collect(), index:0};
+        let node = try!(parser.alt());
+        if parser.index < parser.chars.len() {
+            return Err(format!("unbalanced ')' at {} in /{}/", parser.index, pattern));
+        }
+        let mut program = Vec::new();
+        compile(&node, &mut program);
+        program.push(Inst::Match);
+        Ok(Regex{pattern:String::from(pattern), program:program})
+    }
+
+    pub fn pattern(&self)->&str {
+        self.pattern.as_str()
+    }
+
+    // Length in chars of the longest match anchored at the current position, consuming nothing.
+    pub fn longest<Index:Debug, Tran>(&self, state:&mut State<char, Index=Index, Tran=Tran>)->Option<usize> {
+        let mut seen = vec![0usize; self.program.len()];
+        let mut threads = Vec::new();
+        let mut generation = 1;
+        let mut last = None;
+        if self.add(&mut threads, &mut seen, generation, 0) {
+            last = Some(0);
+        }
+        let tran = state.begin();
+        let mut taken = 0;
+        while !threads.is_empty() {
+            let c = match state.next() {
+                Some(c) => c,
+                None => break,
+            };
+            taken += 1;
+            generation += 1;
+            let mut next = Vec::new();
+            let mut matched = false;
+            for &pc in &threads {
+                if let Inst::Char(ref set) = self.program[pc] {
+                    if set.contains(&c) {
+                        matched = self.add(&mut next, &mut seen, generation, pc + 1) || matched;
+                    }
+                }
+            }
+            if matched {
+                last = Some(taken);
+            }
+            threads = next;
+        }
+        state.rollback(tran);
+        last
+    }
+
+    fn add(&self, threads:&mut Vec<usize>, seen:&mut Vec<usize>, generation:usize, pc:usize)->bool {
+        if seen[pc] == generation {
+            return false;
+        }
+        seen[pc] = generation;
+        match self.program[pc] {
+            Inst::Char(_) => {
+                threads.push(pc);
+                false
+            }
+            Inst::Jmp(to) => self.add(threads, seen, generation, to),
+            Inst::Split(x, y) => {
+                let left = self.add(threads, seen, generation, x);
+                self.add(threads, seen, generation, y) || left
+            }
+            Inst::Match => true,
+        }
+    }
+}
+
+pub fn regex<Index:Debug+Display+'static, Tran:'static>(pattern:&str) -> Parser<char, String, Index, Tran> {
+    match Regex::new(pattern) {
+        Ok(re) => regex_of(re),
+        Err(description) => panic!("{}", description),
+    }
+}
+
+pub fn regex_of<Index:Debug+Display+'static, Tran:'static>(re:Regex) -> Parser<char, String, Index, Tran> {
+    abc!(move |state: &mut State<char, Index=Index, Tran=Tran>|->Status<String, Index>{
+        match re.longest(state) {
+            Some(size) => {
+                let mut data = String::new();
+                for _ in 0..size {
+                    data.push(state.next().unwrap());
+                }
+                Ok(data)
+            }
+            None => {
+                let description = format!("<expect /{}/ at {}>", re.pattern(), state.pos());
+                Err(ParsecError::new(state.pos(), description))
+            }
+        }
+    })
+}
+
+fn compile(node:&Node, program:&mut Vec<Inst>) {
+    match *node {
+        Node::Class(ref set) => program.push(Inst::Char(set.clone())),
+        Node::Concat(ref nodes) => {
+            for n in nodes {
+                compile(n, program);
+            }
+        }
+        Node::Alt(ref nodes) => {
+            let mut jumps = Vec::new();
+            for (i, n) in nodes.iter().enumerate() {
+                if i + 1 == nodes.len() {
+                    compile(n, program);
+                } else {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(n, program);
+                    jumps.push(program.len());
+                    program.push(Inst::Jmp(0));
+                    program[split] = Inst::Split(split + 1, program.len());
+                }
+            }
+            let end = program.len();
+            for j in jumps {
+                program[j] = Inst::Jmp(end);
+            }
+        }
+        Node::Repeat(ref n, min, max) => {
+            for _ in 0..min {
+                compile(n, program);
+            }
+            match max {
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(n, program);
+                    program.push(Inst::Jmp(split));
+                    program[split] = Inst::Split(split + 1, program.len());
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in min..max {
+                        splits.push(program.len());
+                        program.push(Inst::Split(0, 0));
+                        compile(n, program);
+                    }
+                    let end = program.len();
+                    for s in splits {
+                        program[s] = Inst::Split(s + 1, end);
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct PatternParser {
+    chars: Vec<char>,
+    index: usize,
+}
+
+impl PatternParser {
+    fn peek(&self)->Option<char> {
+        self.chars.get(self.index).cloned()
+    }
+
+    fn next(&mut self)->Option<char> {
+        let re = self.peek();
+        if re.is_some() {
+            self.index += 1;
+        }
+        re
+    }
+
+    fn err(&self, description:&str)->String {
+        format!("{} at {} in /{}/", description, self.index, self.chars.iter().cloned().collect::<String>())
+    }
+
+    fn alt(&mut self)->Result<Node, String> {
+        let mut nodes = vec![try!(self.concat())];
+        while self.peek() == Some('|') {
+            self.index += 1;
+            nodes.push(try!(self.concat()));
+        }
+        if nodes.len() == 1 {
+            Ok(nodes.pop().unwrap())
+        } else {
+            Ok(Node::Alt(nodes))
+        }
+    }
+
+    fn concat(&mut self)->Result<Node, String> {
+        let mut nodes = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some('|') | Some(')') => return Ok(Node::Concat(nodes)),
+                _ => nodes.push(try!(self.repeat())),
+            }
+        }
+    }
+
+    fn repeat(&mut self)->Result<Node, String> {
+        let mut node = try!(self.atom());
+        loop {
+            let (min, max) = match self.peek() {
+                Some('*') => (0, None),
+                Some('+') => (1, None),
+                Some('?') => (0, Some(1)),
+                Some('{') => {
+                    self.index += 1;
+                    let min = try!(self.number());
+                    let max = if self.peek() == Some(',') {
+                        self.index += 1;
+                        if self.peek() == Some('}') { None } else { Some(try!(self.number())) }
+                    } else {
+                        Some(min)
+                    };
+                    if self.peek() != Some('}') {
+                        return Err(self.err("expect '}'"));
+                    }
+                    if max.map_or(false, |max| max < min) {
+                        return Err(self.err("bad repeat range"));
+                    }
+                    (min, max)
+                }
+                _ => return Ok(node),
+            };
+            self.index += 1;
+            node = Node::Repeat(Box::new(node), min, max);
+        }
+    }
+
+    fn number(&mut self)->Result<usize, String> {
+        let start = self.index;
+        while self.peek().map_or(false, |c| c.is_digit(10)) {
+            self.index += 1;
+        }
+        let digits = self.chars[start..self.index].iter().cloned().collect::<String>();
+        digits.parse::<usize>().map_err(|_| self.err("expect number"))
+    }
+
+    fn atom(&mut self)->Result<Node, String> {
+        match self.next() {
+            Some('(') => {
+                if self.peek() == Some('?') {
+                    self.index += 1;
+                    if self.next() != Some(':') {
+                        return Err(self.err("expect ':' after '(?'"));
+                    }
+                }
+                let node = try!(self.alt());
+                if self.next() != Some(')') {
+                    return Err(self.err("expect ')'"));
+                }
+                Ok(node)
+            }
+            Some('[') => self.class().map(Node::Class),
+            Some('.') => Ok(Node::Class(CharSet::chars("\n").negate())),
+            Some('\\') => self.escape().map(Node::Class),
+            Some(c) if "*+?{".contains(c) => Err(self.err("nothing to repeat")),
+            Some(c) if "^$".contains(c) => Err(self.err("anchors are not supported")),
+            Some(c) => Ok(Node::Class(CharSet::range(c..=c))),
+            None => Err(self.err("unexpected end")),
+        }
+    }
+
+    fn escape(&mut self)->Result<CharSet, String> {
+        let digit = CharSet::range('0'..='9');
+        let word = CharSet::range('a'..='z').with_range('A'..='Z').with_range('0'..='9').with_chars("_");
+        let space = CharSet::category(Category::Whitespace);
+        match self.next() {
+            Some('d') => Ok(digit),
+            Some('D') => Ok(digit.negate()),
+            Some('w') => Ok(word),
+            Some('W') => Ok(word.negate()),
+            Some('s') => Ok(space),
+            Some('S') => Ok(space.negate()),
+            Some('n') => Ok(CharSet::chars("\n")),
+            Some('r') => Ok(CharSet::chars("\r")),
+            Some('t') => Ok(CharSet::chars("\t")),
+            Some(c) if !c.is_alphanumeric() => Ok(CharSet::range(c..=c)),
+            Some(_) => Err(self.err("unknown escape")),
+            None => Err(self.err("unexpected end")),
+        }
+    }
+
+    fn class(&mut self)->Result<CharSet, String> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.index += 1;
+        }
+        let mut set = CharSet::new();
+        let mut first = true;
+        loop {
+            let lo = match self.next() {
+                None => return Err(self.err("expect ']'")),
+                Some(']') if !first => break,
+                Some('\\') => {
+                    let escaped = try!(self.escape());
+                    set = set.union(escaped);
+                    first = false;
+                    continue;
+                }
+                Some(c) => c,
+            };
+            first = false;
+            if self.peek() == Some('-') && self.chars.get(self.index + 1).map_or(false, |c| *c != ']') {
+                self.index += 1;
+                let hi = match self.next() {
+                    Some('\\') => match self.next() {
+                        Some(c) if !c.is_alphanumeric() => c,
+                        _ => return Err(self.err("bad range end")),
+                    },
+                    Some(c) => c,
+                    None => return Err(self.err("expect ']'")),
+                };
+                if hi < lo {
+                    return Err(self.err("bad range"));
+                }
+                set = set.with_range(lo..=hi);
+            } else {
+                set = set.with_range(lo..=lo);
+            }
+        }
+        Ok(if negated { set.negate() } else { set })
+    }
+}
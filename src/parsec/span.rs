@@ -0,0 +1,91 @@
+use parsec::{State, Status, Parser, Parsec};
+use std::fmt::{Formatter, Debug, Display};
+use std::fmt;
+use std::sync::Arc;
+
+// Range of positions a parser consumed, end exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash)]
+pub struct Span<Index=usize> {
+    pub start: Index,
+    pub end: Index,
+}
+
+impl<Index:PartialOrd+Clone> Span<Index> {
+    pub fn new(start:Index, end:Index)->Span<Index> {
+        Span{start:start, end:end}
+    }
+
+    // Smallest span covering both, e.g. from the first to the last child of a node.
+    pub fn join(&self, other:&Span<Index>)->Span<Index> {
+        let start = if other.start < self.start { other.start.clone() } else { self.start.clone() };
+        let end = if other.end > self.end { other.end.clone() } else { self.end.clone() };
+        Span{start:start, end:end}
+    }
+
+    pub fn contains(&self, pos:&Index)->bool {
+        self.start <= *pos && *pos < self.end
+    }
+
+    pub fn is_empty(&self)->bool {
+        self.start >= self.end
+    }
+}
+
+impl<Index:Display> Display for Span<Index> {
+    fn fmt(&self, formatter:&mut Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{}..{}", self.start, self.end)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Located<R, Index=usize> {
+    pub value: R,
+    pub span: Span<Index>,
+}
+
+impl<R, Index> Located<R, Index> {
+    pub fn map<S, F:FnOnce(R)->S>(self, f:F)->Located<S, Index> {
+        Located{value:f(self.value), span:self.span}
+    }
+}
+
+pub fn spanned<T:'static, R:'static, X:'static, Index:Debug+Clone+PartialEq+'static, Tran:'static>(p:X)
+        ->Parser<T, (R, Span<Index>), Index, Tran>
+where T:Clone, R:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
+    abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<(R, Span<Index>), Index>{
+        let start = state.pos();
+        let re = try!(p.parse(state));
+        // An empty match ends where it starts.
+        let end = if state.pos() == start { start.clone() } else { state.end_pos() };
+        Ok((re, Span{start:start, end:end}))
+    })
+}
+
+pub fn located<T:'static, R:'static, X:'static, Index:Debug+Clone+PartialEq+'static, Tran:'static>(p:X)
+        ->Parser<T, Located<R, Index>, Index, Tran>
+where T:Clone, R:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
+    let p = spanned(p);
+    abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<Located<R, Index>, Index>{
+        p.parse(state).map(|(value, span)| Located{value:value, span:span})
+    })
+}
+
+// Builds a node from the result and its span in one step, e.g. an AST constructor taking a span.
+pub fn located_by<T:'static, R:'static, S:'static, X:'static, Index:Debug+Clone+PartialEq+'static, Tran:'static, F:'static>
+        (p:X, f:F)->Parser<T, S, Index, Tran>
+where T:Clone, R:Clone, S:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone, F:Fn(R, Span<Index>)->S {
+    let p = spanned(p);
+    abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<S, Index>{
+        p.parse(state).map(|(value, span)| f(value, span))
+    })
+}
+
+// Only the span, for rules whose value is the source text itself.
+pub fn span_of<T:'static, R:'static, X:'static, Index:Debug+Clone+PartialEq+'static, Tran:'static>(p:X)
+        ->Parser<T, Span<Index>, Index, Tran>
+where T:Clone, R:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
+    let p = spanned(p);
+    abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<Span<Index>, Index>{
+        p.parse(state).map(|(_, span)| span)
+    })
+}
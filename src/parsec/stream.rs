@@ -0,0 +1,98 @@
+use parsec::{State, Status};
+use std::collections::VecDeque;
+
+// State over an iterator that pulls elements on demand and forgets everything before the
+// current position once no transaction is open, so long inputs run in bounded memory.
+// Positions are absolute; seeking back before the kept window fails.
+pub struct StreamState<I:Iterator> {
+    source: I,
+    buffer: VecDeque<I::Item>,
+    offset: usize,
+    index: usize,
+    trans: Vec<usize>,
+}
+
+impl<I:Iterator> StreamState<I> {
+    pub fn new(source:I)->StreamState<I> {
+        StreamState{source:source, buffer:VecDeque::new(), offset:0, index:0, trans:Vec::new()}
+    }
+
+    // Number of elements held in memory right now.
+    pub fn buffered(&self)->usize {
+        self.buffer.len()
+    }
+
+    fn fill(&mut self, to:usize)->bool {
+        while self.offset + self.buffer.len() <= to {
+            match self.source.next() {
+                Some(item) => self.buffer.push_back(item),
+                None => return false,
+            }
+        }
+        true
+    }
+
+    fn compact(&mut self) {
+        if self.trans.is_empty() {
+            while self.offset < self.index && !self.buffer.is_empty() {
+                self.buffer.pop_front();
+                self.offset += 1;
+            }
+        }
+    }
+
+    fn close(&mut self, tran:usize) {
+        if let Some(at) = self.trans.iter().rposition(|t| *t == tran) {
+            self.trans.remove(at);
+        }
+        self.compact();
+    }
+}
+
+impl<I:Iterator> State<I::Item> for StreamState<I> where I::Item:Clone {
+    type Index = usize;
+    type Tran = usize;
+    fn pos(&self)->usize {
+        self.index
+    }
+    fn seek_to(&mut self, to:usize)->bool {
+        if to < self.offset || (to > 0 && !self.fill(to - 1)) {
+            return false;
+        }
+        self.index = to;
+        self.compact();
+        true
+    }
+    fn next(&mut self)->Option<I::Item> {
+        let index = self.index;
+        if !self.fill(index) {
+            return None;
+        }
+        let item = self.buffer[index - self.offset].clone();
+        self.index += 1;
+        self.compact();
+        Some(item)
+    }
+    fn next_by(&mut self, pred:&Fn(&I::Item)->bool)->Status<I::Item, usize> {
+        let index = self.index;
+        if !self.fill(index) {
+            return Err(self.err(String::from("eof")));
+        }
+        if pred(&self.buffer[index - self.offset]) {
+            Ok(self.next().unwrap())
+        } else {
+            Err(self.err(String::from("predicate failed")))
+        }
+    }
+    fn begin(&mut self)->usize {
+        self.trans.push(self.index);
+        self.index
+    }
+    fn commit(&mut self, tran:usize) {
+        self.close(tran);
+    }
+    fn rollback(&mut self, tran:usize) {
+        self.index = tran;
+        self.close(tran);
+    }
+}
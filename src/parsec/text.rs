@@ -1,26 +1,33 @@
-use parsec::{State, Status, Parsec, Monad, Parser, parser};
-use parsec::combinator::{either, try, many1};
-use parsec::atom::{OneOf, pack, eq, one_of};
+use parsec::{State, Status, Monad, Parser, Parsec};
+use parsec::memo::Memo;
+use parsec::combinator::{either, try};
+use parsec::atom::{pack, eq, satisfy, take_while1};
+use std::fmt::{Debug, Display};
 use std::sync::Arc;
 use std::boxed::Box;
 
+// Index is the byte offset into buffer, so it always sits on a char boundary.
 pub struct StringState {
     buffer:String,
     index:usize,
+    tran:Option<usize>,
+    memo:Memo<usize>,
 }
 
 impl StringState {
     pub fn new(data:String)->StringState {
-        StringState{buffer:data, index:0}
+        StringState{buffer:data, index:0, tran:None, memo:Memo::new()}
     }
 }
 
 impl State<char> for StringState {
+    type Index = usize;
+    type Tran = usize;
     fn pos(&self)-> usize {
         self.index
     }
     fn seek_to(&mut self, to:usize)->bool{
-        if 0 as usize <= to && to < self.buffer.len() {
+        if to <= self.buffer.len() && self.buffer.is_char_boundary(to) {
             self.index = to;
             true
         } else {
@@ -28,80 +35,91 @@ impl State<char> for StringState {
         }
     }
     fn next(&mut self)->Option<char> {
-        let next= self.index+1;
-        let re = self.buffer.chars().nth(next);
-        if re.is_some() {
-            self.index = next;
+        let re = self.buffer[self.index..].chars().next();
+        if let Some(c) = re {
+            self.index += c.len_utf8();
         }
         re
     }
-    fn next_by(&mut self, pred:&Fn(&char)->bool)->Status<char> {
+    fn next_by(&mut self, pred:&Fn(&char)->bool)->Status<char, usize> {
+        let pos = self.index;
         let data = self.next();
         if data.is_none() {
             return Err(self.err(String::from("eof")));
         } else {
             let item = data.unwrap();
             if pred(&item) {
-                return Ok(item.clone());
+                return Ok(item);
             }
         }
+        self.index = pos;
         return Err(self.err(String::from("predicate failed")));
     }
+    fn begin(&mut self)-> usize {
+        if self.tran.is_none() {
+            self.tran = Some(self.index);
+        }
+        self.index
+    }
+    fn commit(&mut self, tran:usize) {
+        if self.tran.map_or(false, |t| t == tran) {
+            self.tran = None;
+        }
+    }
+    fn rollback(&mut self, tran:usize) {
+        self.index = tran;
+        if self.tran.map_or(false, |t| t == tran) {
+            self.tran = None;
+        }
+    }
+    fn memo(&mut self)->Option<&mut Memo<usize>> {
+        Some(&mut self.memo)
+    }
 }
 
-pub fn space() -> OneOf<char> {
-    char(' ')
+pub fn space<Index:Debug+Display+'static, Tran:'static>() -> Parser<char, char, Index, Tran> {
+    eq(' ')
 }
 
-pub fn white_space() -> Parser<char, char> {
-    abc!(|state: &mut State<char>| -> Status<char>{
-        state.next_by(&|x:&char| x.is_whitespace())
-    })
+pub fn white_space<Index:Debug+'static, Tran:'static>() -> Parser<char, char, Index, Tran> {
+    satisfy(|x:&char| x.is_whitespace())
 }
 
-pub fn newline() -> Parser<char, String> {
-    abc!(|state: &mut State<char>| -> Status<String>{
+pub fn newline<Index:PartialEq+Debug+Display+'static, Tran:'static>() -> Parser<char, String, Index, Tran> {
+    abc!(|state: &mut State<char, Index=Index, Tran=Tran>| -> Status<String, Index>{
         let rel = eq('\r');
         let nl = eq('\n');
         let thn = either(try(nl.clone()).then(pack(String::from("\r\n"))),
                                 pack(String::from("\r")));
-        either(rel.then(thn.clone()), nl.then(pack(String::from("\n")))).parse(state)
+        either(try(rel).then(thn.clone()), nl.then(pack(String::from("\n")))).parse(state)
     })
 }
 
-pub fn digit() -> Parser<char, char> {
-    abc!(|state: &mut State<char>| -> Status<char>{
-        state.next_by(&|x:&char| x.is_numeric())
-    })
+pub fn digit<Index:Debug+'static, Tran:'static>() -> Parser<char, char, Index, Tran> {
+    satisfy(|x:&char| x.is_numeric())
 }
 
-pub fn alpha() -> Parser<char, char> {
-    abc!(|state: &mut State<char>| -> Status<char>{
-        state.next_by(&|x:&char| x.is_alphabetic())
-    })
+pub fn alpha<Index:Debug+'static, Tran:'static>() -> Parser<char, char, Index, Tran> {
+    satisfy(|x:&char| x.is_alphabetic())
 }
 
-pub fn alphanumeric() -> Parser<char, char> {
-    abc!(|state: &mut State<char>| -> Status<char>{
-        state.next_by(&|x:&char| x.is_alphanumeric())
-    })
+pub fn alphanumeric<Index:Debug+'static, Tran:'static>() -> Parser<char, char, Index, Tran> {
+    satisfy(|x:&char| x.is_alphanumeric())
 }
 
-pub fn control() -> Parser<char, char> {
-    abc!(|state: &mut State<char>| -> Status<char>{
-        state.next_by(&|x:&char| x.is_control())
-    })
+pub fn control<Index:Debug+'static, Tran:'static>() -> Parser<char, char, Index, Tran> {
+    satisfy(|x:&char| x.is_control())
 }
 
-pub fn uinteger() -> Parser<char, String> {
-    abc!(|state: &mut State<char>|-> Status<String> {
-        let data = try!(many1(digit)(state))
-        Ok(.iter().cloned().collect::<String>())
+pub fn uinteger<Index:Debug+Display+'static, Tran:'static>() -> Parser<char, String, Index, Tran> {
+    abc!(|state: &mut State<char, Index=Index, Tran=Tran>|-> Status<String, Index> {
+        let data = try!(take_while1(|x:&char| x.is_digit(10))(state));
+        Ok(data.iter().cloned().collect::<String>())
     })
 }
 
-pub fn integer() ->Parser<char, String>{
-    abc!(|state: &mut State<char>|->Status<String>{
+pub fn integer<Index:Debug+Display+'static, Tran:'static>() ->Parser<char, String, Index, Tran>{
+    abc!(|state: &mut State<char, Index=Index, Tran=Tran>|->Status<String, Index>{
         let mut re = String::from("");
         if try(eq('-'))(state).is_ok() {
             re.push_str("-");
@@ -112,24 +130,24 @@ pub fn integer() ->Parser<char, String>{
     })
 }
 
-pub fn ufloat() -> Parser<char, String> {
-    abc!(|state: &mut State<char>|->Status<String>{
+pub fn ufloat<Index:PartialEq+Debug+Display+'static, Tran:'static>() -> Parser<char, String, Index, Tran> {
+    abc!(|state: &mut State<char, Index=Index, Tran=Tran>|->Status<String, Index>{
         let mut re = try!(either(uinteger(), pack(String::from("0")))(state));
-        try!(eq('.')(state));
-        let x = try!(uinter(state));
-        re.push_str(x);
+        re.push(try!(eq('.')(state)));
+        let x = try!(uinteger()(state));
+        re.push_str(x.as_str());
         Ok(re)
     })
 }
 
-pub fn float() -> Parser<char, String>{
-    abc!(|state:&mut State<char>|->Status<String>{
+pub fn float<Index:PartialEq+Debug+Display+'static, Tran:'static>() -> Parser<char, String, Index, Tran>{
+    abc!(|state:&mut State<char, Index=Index, Tran=Tran>|->Status<String, Index>{
         let mut re = String::from("");
         if try(eq('-'))(state).is_ok() {
             re.push_str("-");
         }
-        x = ufloat()(state)
-        re.push_str(x)
+        let x = try!(ufloat()(state));
+        re.push_str(x.as_str());
         Ok(re)
     })
 }
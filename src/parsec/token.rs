@@ -0,0 +1,151 @@
+use parsec::{State, Status};
+use parsec::memo::Memo;
+use parsec::span::Span;
+use std::fmt::{Formatter, Display};
+use std::fmt;
+
+// Position in a TokenState: the ordinal of the next token and its source span. Zero-width or
+// shared spans can't tell tokens apart, so the ordinal is what orders and identifies positions.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Hash)]
+pub struct TokenPos {
+    index: usize,
+    span: Span,
+}
+
+impl TokenPos {
+    // Index of the token in the token vector.
+    pub fn offset(&self)->usize {
+        self.index
+    }
+
+    pub fn span(&self)->Span {
+        self.span
+    }
+}
+
+impl Display for TokenPos {
+    fn fmt(&self, formatter:&mut Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{}", self.span)
+    }
+}
+
+impl Span<TokenPos> {
+    // The source text a spanned token parser covered.
+    pub fn source(&self)->Span {
+        Span::new(self.start.span.start, self.end.span.end)
+    }
+}
+
+// State over lexer output for two-phase parsing. Rules match on the tokens, and positions carry
+// the source span of the next token, or an empty span at the end of the input. Like every
+// state, a failed match is reported at the element that failed, so errors point at the
+// offending token.
+pub struct TokenState<Tok> {
+    tokens: Vec<(Tok, Span)>,
+    index: usize,
+    end: usize,
+    tran: Option<usize>,
+    memo: Memo<TokenPos>,
+}
+
+impl<Tok> TokenState<Tok> {
+    pub fn new(tokens:Vec<(Tok, Span)>)->TokenState<Tok> {
+        let end = tokens.last().map_or(0, |&(_, ref span)| span.end);
+        TokenState::with_end(tokens, end)
+    }
+
+    // end is the source length, so trailing whitespace or comments still count for eof errors.
+    pub fn with_end(tokens:Vec<(Tok, Span)>, end:usize)->TokenState<Tok> {
+        TokenState{tokens:tokens, index:0, end:end, tran:None, memo:Memo::new()}
+    }
+
+    pub fn tokens(&self)->&[(Tok, Span)] {
+        &self.tokens
+    }
+
+    // Index of the next token in the token vector.
+    pub fn offset(&self)->usize {
+        self.index
+    }
+
+    pub fn rest(&self)->&[(Tok, Span)] {
+        &self.tokens[self.index..]
+    }
+
+    fn span_at(&self, index:usize)->Span {
+        match self.tokens.get(index) {
+            Some(&(_, ref span)) => span.clone(),
+            None => Span::new(self.end, self.end),
+        }
+    }
+
+    fn pos_at(&self, index:usize)->TokenPos {
+        TokenPos{index:index, span:self.span_at(index)}
+    }
+}
+
+impl<Tok:Clone> State<Tok> for TokenState<Tok> {
+    type Index = TokenPos;
+    type Tran = usize;
+    fn pos(&self)->TokenPos {
+        self.pos_at(self.index)
+    }
+    // An empty span at the end of the last token read, so Span::source of a spanned result
+    // covers the source from its first token to its last.
+    fn end_pos(&self)->TokenPos {
+        let at = match self.index.checked_sub(1) {
+            Some(last) => self.span_at(last).end,
+            None => self.span_at(0).start,
+        };
+        TokenPos{index:self.index, span:Span::new(at, at)}
+    }
+    fn seek_to(&mut self, to:TokenPos)->bool {
+        if to.index > self.tokens.len() {
+            return false;
+        }
+        self.index = to.index;
+        true
+    }
+    fn next(&mut self)->Option<Tok> {
+        if self.index < self.tokens.len() {
+            let item = self.tokens[self.index].0.clone();
+            self.index += 1;
+            Some(item)
+        } else {
+            None
+        }
+    }
+    fn next_by(&mut self, pred:&Fn(&Tok)->bool)->Status<Tok, TokenPos> {
+        if self.index < self.tokens.len() {
+            let item = self.tokens[self.index].0.clone();
+            if pred(&item) {
+                self.index += 1;
+                Ok(item)
+            } else {
+                Err(self.err(String::from("predicate failed")))
+            }
+        } else {
+            Err(self.err(String::from("eof")))
+        }
+    }
+    fn begin(&mut self)->usize {
+        if self.tran.is_none() {
+            self.tran = Some(self.index);
+        }
+        self.index
+    }
+    fn commit(&mut self, tran:usize) {
+        if self.tran.map_or(false, |t| t == tran) {
+            self.tran = None;
+        }
+    }
+    fn rollback(&mut self, tran:usize) {
+        self.index = tran;
+        if self.tran.map_or(false, |t| t == tran) {
+            self.tran = None;
+        }
+    }
+    fn memo(&mut self)->Option<&mut Memo<TokenPos>> {
+        Some(&mut self.memo)
+    }
+}
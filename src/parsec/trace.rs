@@ -0,0 +1,263 @@
+use parsec::{State, Status, Parser, Parsec};
+use parsec::memo::Memo;
+use parsec::profile::Profiler;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::cell::{Cell, RefCell};
+use std::error::Error;
+
+static GLOBAL: AtomicBool = AtomicBool::new(false);
+
+thread_local!(static DEPTH: Cell<usize> = Cell::new(0));
+
+// Global mode prints every traced rule to stderr as it runs, whatever the state is.
+pub fn set_global(on:bool) {
+    GLOBAL.store(on, Ordering::SeqCst);
+}
+
+pub fn is_global()->bool {
+    GLOBAL.load(Ordering::SeqCst)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<Index> {
+    Enter{rule:String, depth:usize, pos:Index},
+    Exit{rule:String, depth:usize, pos:Index, ok:bool, consumed:String, message:Option<String>},
+    // next_by read item at pos and its predicate turned it down.
+    Reject{depth:usize, pos:Index, item:String},
+}
+
+// Events recorded by a TraceState, plus the trail of elements read so far, which is cut back
+// on rollback so each exit reports only the input the rule really consumed.
+pub struct Tracer<Index> {
+    events: Vec<Event<Index>>,
+    stack: Vec<usize>,
+    trail: Vec<(Index, String)>,
+}
+
+impl<Index:PartialOrd+Clone+Debug> Tracer<Index> {
+    pub fn new()->Tracer<Index> {
+        Tracer{events:Vec::new(), stack:Vec::new(), trail:Vec::new()}
+    }
+
+    pub fn enter(&mut self, rule:&str, pos:Index) {
+        let depth = self.stack.len();
+        self.stack.push(self.trail.len());
+        self.events.push(Event::Enter{rule:String::from(rule), depth:depth, pos:pos});
+    }
+
+    pub fn exit(&mut self, rule:&str, pos:Index, message:Option<String>) {
+        let start = self.stack.pop().unwrap_or(0);
+        let consumed = self.trail[start.min(self.trail.len())..].iter()
+            .map(|&(_, ref item)| item.as_str()).collect::<String>();
+        self.events.push(Event::Exit{
+            rule: String::from(rule),
+            depth: self.stack.len(),
+            pos: pos,
+            ok: message.is_none(),
+            consumed: consumed,
+            message: message,
+        });
+    }
+
+    pub fn read(&mut self, pos:Index, item:String) {
+        self.trail.push((pos, item));
+    }
+
+    pub fn reject(&mut self, pos:Index, item:String) {
+        self.events.push(Event::Reject{depth:self.stack.len(), pos:pos, item:item});
+    }
+
+    pub fn rewind(&mut self, pos:&Index) {
+        while self.trail.last().map_or(false, |&(ref p, _)| p >= pos) {
+            self.trail.pop();
+        }
+    }
+
+    pub fn events(&self)->&[Event<Index>] {
+        &self.events
+    }
+
+    pub fn render(&self)->String {
+        let mut re = String::new();
+        for event in &self.events {
+            match *event {
+                Event::Enter{ref rule, depth, ref pos} => {
+                    re.push_str(&format!("{}> {} at {:?}\n", "  ".repeat(depth), rule, pos));
+                }
+                Event::Exit{ref rule, depth, ref pos, ok, ref consumed, ref message} => {
+                    let indent = "  ".repeat(depth);
+                    if ok {
+                        re.push_str(&format!("{}< {} ok at {:?} consumed {:?}\n", indent, rule, pos, consumed));
+                    } else {
+                        re.push_str(&format!("{}< {} failed at {:?} consumed {:?}: {}\n",
+                            indent, rule, pos, consumed, message.as_ref().unwrap()));
+                    }
+                }
+                Event::Reject{depth, ref pos, ref item} => {
+                    re.push_str(&format!("{}! rejected {:?} at {:?}\n", "  ".repeat(depth), item, pos));
+                }
+            }
+        }
+        re
+    }
+
+    // Positions whose Debug form is a number, like the usize of the char states, are JSON
+    // numbers. Others, such as token positions, are their Debug form as a string.
+    pub fn to_json(&self)->String {
+        let events = self.events.iter().map(|event| match *event {
+            Event::Enter{ref rule, depth, ref pos} => format!(
+                "{{\"event\":\"enter\",\"rule\":{},\"depth\":{},\"pos\":{}}}",
+                json_string(rule), depth, json_pos(pos)),
+            Event::Exit{ref rule, depth, ref pos, ok, ref consumed, ref message} => format!(
+                "{{\"event\":\"exit\",\"rule\":{},\"depth\":{},\"pos\":{},\"ok\":{},\"consumed\":{},\"message\":{}}}",
+                json_string(rule), depth, json_pos(pos), ok, json_string(consumed),
+                message.as_ref().map_or(String::from("null"), |m| json_string(m))),
+            Event::Reject{depth, ref pos, ref item} => format!(
+                "{{\"event\":\"reject\",\"depth\":{},\"pos\":{},\"item\":{}}}",
+                depth, json_pos(pos), json_string(item)),
+        }).collect::<Vec<String>>();
+        format!("[{}]", events.join(","))
+    }
+}
+
+fn json_pos<Index:Debug>(pos:&Index)->String {
+    let text = format!("{:?}", pos);
+    if !text.is_empty() && text.chars().all(|c| c.is_ascii_digit()) {
+        text
+    } else {
+        json_string(&text)
+    }
+}
+
+fn json_string(data:&str)->String {
+    let mut re = String::from("\"");
+    for c in data.chars() {
+        match c {
+            '"' => re.push_str("\\\""),
+            '\\' => re.push_str("\\\\"),
+            '\n' => re.push_str("\\n"),
+            '\r' => re.push_str("\\r"),
+            '\t' => re.push_str("\\t"),
+            c if (c as u32) < 0x20 => re.push_str(&format!("\\u{:04x}", c as u32)),
+            c => re.push(c),
+        }
+    }
+    re.push('"');
+    re
+}
+
+// Wraps any state and records what the trace rules inside it do.
+pub struct TraceState<S, Index> {
+    inner: S,
+    tracer: Tracer<Index>,
+}
+
+impl<S, Index:PartialOrd+Clone+Debug> TraceState<S, Index> {
+    pub fn new(inner:S)->TraceState<S, Index> {
+        TraceState{inner:inner, tracer:Tracer::new()}
+    }
+
+    pub fn log(&self)->&Tracer<Index> {
+        &self.tracer
+    }
+
+    pub fn into_inner(self)->S {
+        self.inner
+    }
+}
+
+impl<T:Display, S> State<T> for TraceState<S, S::Index> where S:State<T>, S::Index:PartialOrd+Clone {
+    type Index = S::Index;
+    type Tran = S::Tran;
+    fn pos(&self)->S::Index {
+        self.inner.pos()
+    }
+    fn end_pos(&self)->S::Index {
+        self.inner.end_pos()
+    }
+    fn seek_to(&mut self, to:S::Index)->bool {
+        let re = self.inner.seek_to(to);
+        self.tracer.rewind(&self.inner.pos());
+        re
+    }
+    fn next(&mut self)->Option<T> {
+        let pos = self.inner.pos();
+        let re = self.inner.next();
+        if let Some(ref item) = re {
+            self.tracer.read(pos, item.to_string());
+        }
+        re
+    }
+    fn next_by(&mut self, pred:&Fn(&T)->bool)->Status<T, S::Index> {
+        let pos = self.inner.pos();
+        let seen = RefCell::new(None);
+        let re = self.inner.next_by(&|item:&T| {
+            *seen.borrow_mut() = Some(item.to_string());
+            pred(item)
+        });
+        match (&re, seen.into_inner()) {
+            (&Ok(ref item), _) => self.tracer.read(pos, item.to_string()),
+            (&Err(_), Some(item)) => self.tracer.reject(pos, item),
+            _ => {}
+        }
+        re
+    }
+    fn begin(&mut self)->S::Tran {
+        self.inner.begin()
+    }
+    fn commit(&mut self, tran:S::Tran) {
+        self.inner.commit(tran)
+    }
+    fn rollback(&mut self, tran:S::Tran) {
+        self.inner.rollback(tran);
+        self.tracer.rewind(&self.inner.pos());
+    }
+    fn memo(&mut self)->Option<&mut Memo<S::Index>> {
+        self.inner.memo()
+    }
+    fn tracer(&mut self)->Option<&mut Tracer<S::Index>> {
+        Some(&mut self.tracer)
+    }
+    fn profiler(&mut self)->Option<&mut Profiler> {
+        self.inner.profiler()
+    }
+}
+
+pub fn trace<T:'static, R:'static, X:'static, Index:Debug+Clone+PartialOrd+'static, Tran:'static>
+        (name:&str, p:X)->Parser<T, R, Index, Tran>
+where T:Clone, R:Clone, X:Parsec<T, R, Index=Index, Tran=Tran>+Clone {
+    let name = String::from(name);
+    abc!(move |state: &mut State<T, Index=Index, Tran=Tran>|->Status<R, Index>{
+        let global = is_global();
+        if global {
+            let depth = DEPTH.with(|d| { d.set(d.get() + 1); d.get() - 1 });
+            eprintln!("{}> {} at {:?}", "  ".repeat(depth), name, state.pos());
+        }
+        let pos = state.pos();
+        if let Some(tracer) = state.tracer() {
+            tracer.enter(&name, pos);
+        }
+        if let Some(profiler) = state.profiler() {
+            profiler.enter(&name);
+        }
+        let re = p.parse(state);
+        let pos = state.pos();
+        if let Some(profiler) = state.profiler() {
+            profiler.exit(re.is_ok());
+        }
+        let message = re.as_ref().err().map(|e| String::from(e.description()));
+        if global {
+            let depth = DEPTH.with(|d| { d.set(d.get() - 1); d.get() });
+            match message {
+                None => eprintln!("{}< {} ok at {:?}", "  ".repeat(depth), name, pos),
+                Some(ref m) => eprintln!("{}< {} failed at {:?}: {}", "  ".repeat(depth), name, pos, m),
+            }
+        }
+        if let Some(tracer) = state.tracer() {
+            tracer.exit(&name, pos, message);
+        }
+        re
+    })
+}
@@ -0,0 +1,305 @@
+use std::fmt::{Formatter, Display};
+use std::fmt;
+use std::mem;
+use std::ops::Add;
+use std::rc::Rc;
+
+// Pretty printer documents after Wadler's "A prettier printer" and Leijen's wl-pprint, the
+// printing half next to parsec. A Doc is text with line breaks that a group may lay out flat
+// when it fits in the width:
+//   let call = group(text("f(") + nest(2, linebreak() + vsep(punctuate(text(","), args))) + text(")"));
+//   call.pretty(40)  or  format!("{:40}", call)
+// text() should not hold newlines, string() turns them into hardline.
+#[derive(Debug)]
+enum Node {
+    Nil,
+    Text(String),
+    // Flat as the text, or else a newline.
+    Line(&'static str),
+    // A newline in any layout, no group holding one is flat.
+    HardLine,
+    Cat(Doc, Doc),
+    Nest(usize, Doc),
+    Group(Doc),
+}
+
+#[derive(Debug, Clone)]
+pub struct Doc(Rc<Node>);
+
+// hcat of a long list is a chain as deep as the list, so children are taken apart on a stack
+// instead of by recursive drops. Each taken child is swapped for one shared Nil.
+impl Drop for Node {
+    fn drop(&mut self) {
+        let mut stack = Vec::new();
+        let mut hole = None;
+        take_children(self, &mut stack, &mut hole);
+        while let Some(doc) = stack.pop() {
+            if let Ok(mut node) = Rc::try_unwrap(doc.0) {
+                take_children(&mut node, &mut stack, &mut hole);
+            }
+        }
+    }
+}
+
+fn take_children(node:&mut Node, stack:&mut Vec<Doc>, hole:&mut Option<Doc>) {
+    let (x, y) = match *node {
+        Node::Cat(ref mut x, ref mut y) => (x, Some(y)),
+        Node::Nest(_, ref mut x) | Node::Group(ref mut x) => (x, None),
+        _ => return,
+    };
+    for doc in Some(x).into_iter().chain(y) {
+        if let Node::Nil = *doc.0 {
+            continue;
+        }
+        let nil = hole.get_or_insert_with(nil).clone();
+        stack.push(mem::replace(doc, nil));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+impl Doc {
+    fn new(node:Node)->Doc {
+        Doc(Rc::new(node))
+    }
+
+    // x <> y
+    pub fn append(self, other:Doc)->Doc {
+        match (&*self.0, &*other.0) {
+            (&Node::Nil, _) => return other,
+            (_, &Node::Nil) => return self,
+            _ => {}
+        }
+        Doc::new(Node::Cat(self, other))
+    }
+
+    // x <+> y
+    pub fn space(self, other:Doc)->Doc {
+        self.append(text(" ")).append(other)
+    }
+
+    // x <$> y
+    pub fn line(self, other:Doc)->Doc {
+        self.append(line()).append(other)
+    }
+
+    // x </> y
+    pub fn softline(self, other:Doc)->Doc {
+        self.append(softline()).append(other)
+    }
+
+    // x <$$> y
+    pub fn linebreak(self, other:Doc)->Doc {
+        self.append(linebreak()).append(other)
+    }
+
+    // x <//> y
+    pub fn softbreak(self, other:Doc)->Doc {
+        self.append(softbreak()).append(other)
+    }
+
+    // Lays the document out in width columns: every group is flat when the rest of its line
+    // still fits, otherwise its lines break. Lines carry no trailing spaces.
+    pub fn pretty(&self, width:usize)->String {
+        let mut re = String::new();
+        let mut column = 0;
+        // Indentation of the line just started, written before its first text.
+        let mut pending = None;
+        let mut stack = vec![(0, Mode::Break, self)];
+        while let Some((indent, mode, doc)) = stack.pop() {
+            match *doc.0 {
+                Node::Nil => {}
+                Node::Text(ref text) => {
+                    if let Some(indent) = pending.take() {
+                        re.extend((0..indent).map(|_| ' '));
+                    }
+                    re.push_str(text);
+                    column += text.chars().count();
+                }
+                Node::Line(flat) if mode == Mode::Flat => {
+                    if !flat.is_empty() {
+                        if let Some(indent) = pending.take() {
+                            re.extend((0..indent).map(|_| ' '));
+                        }
+                        re.push_str(flat);
+                        column += flat.chars().count();
+                    }
+                }
+                Node::Line(_) | Node::HardLine => {
+                    re.push('\n');
+                    pending = Some(indent);
+                    column = indent;
+                }
+                Node::Cat(ref x, ref y) => {
+                    stack.push((indent, mode, y));
+                    stack.push((indent, mode, x));
+                }
+                Node::Nest(i, ref x) => stack.push((indent + i, mode, x)),
+                Node::Group(ref x) => {
+                    let mode = if mode == Mode::Flat || fits(width as isize - column as isize, (indent, Mode::Flat, x), &stack) {
+                        Mode::Flat
+                    } else {
+                        Mode::Break
+                    };
+                    stack.push((indent, mode, x));
+                }
+            }
+        }
+        re
+    }
+}
+
+// Whether the text up to the next newline fits in rest columns, starting with first and going
+// on with what is left to print. Groups still to come count as broken.
+fn fits(mut rest:isize, first:(usize, Mode, &Doc), stack:&[(usize, Mode, &Doc)])->bool {
+    let mut local = vec![first];
+    let mut after = stack.iter().rev();
+    loop {
+        if rest < 0 {
+            return false;
+        }
+        let (indent, mode, doc) = match local.pop() {
+            Some(x) => x,
+            None => match after.next() {
+                Some(&x) => x,
+                None => return true,
+            },
+        };
+        match *doc.0 {
+            Node::Nil => {}
+            Node::Text(ref text) => rest -= text.chars().count() as isize,
+            Node::Line(flat) if mode == Mode::Flat => rest -= flat.chars().count() as isize,
+            Node::Line(_) => return true,
+            Node::HardLine => return mode == Mode::Break,
+            Node::Cat(ref x, ref y) => {
+                local.push((indent, mode, y));
+                local.push((indent, mode, x));
+            }
+            Node::Nest(i, ref x) => local.push((indent + i, mode, x)),
+            Node::Group(ref x) => local.push((indent, mode, x)),
+        }
+    }
+}
+
+impl Add for Doc {
+    type Output = Doc;
+    fn add(self, other:Doc)->Doc {
+        self.append(other)
+    }
+}
+
+// Renders at the formatter width, {:40}, or at 80 columns.
+impl Display for Doc {
+    fn fmt(&self, formatter:&mut Formatter)->Result<(), fmt::Error> {
+        write!(formatter, "{}", self.pretty(formatter.width().unwrap_or(80)))
+    }
+}
+
+pub fn nil()->Doc {
+    Doc::new(Node::Nil)
+}
+
+pub fn text(text:&str)->Doc {
+    if text.is_empty() {
+        nil()
+    } else {
+        Doc::new(Node::Text(String::from(text)))
+    }
+}
+
+// Text that may hold newlines, each one a hardline.
+pub fn string(text:&str)->Doc {
+    let mut lines = text.split('\n');
+    let first = self::text(lines.next().unwrap_or(""));
+    lines.fold(first, |re, x| re.append(hardline()).append(self::text(x)))
+}
+
+// A newline, or a space when flat.
+pub fn line()->Doc {
+    Doc::new(Node::Line(" "))
+}
+
+// A newline, or nothing when flat.
+pub fn linebreak()->Doc {
+    Doc::new(Node::Line(""))
+}
+
+// A space if the rest fits on the line, a newline otherwise.
+pub fn softline()->Doc {
+    group(line())
+}
+
+pub fn softbreak()->Doc {
+    group(linebreak())
+}
+
+pub fn hardline()->Doc {
+    Doc::new(Node::HardLine)
+}
+
+// Lines inside doc start i columns further in.
+pub fn nest(i:usize, doc:Doc)->Doc {
+    Doc::new(Node::Nest(i, doc))
+}
+
+// doc on one line if it fits, else with its lines broken.
+pub fn group(doc:Doc)->Doc {
+    Doc::new(Node::Group(doc))
+}
+
+fn fold<F>(docs:Vec<Doc>, f:F)->Doc where F:Fn(Doc, Doc)->Doc {
+    let mut docs = docs.into_iter();
+    match docs.next() {
+        Some(first) => docs.fold(first, f),
+        None => nil(),
+    }
+}
+
+pub fn hcat(docs:Vec<Doc>)->Doc {
+    fold(docs, Doc::append)
+}
+
+pub fn hsep(docs:Vec<Doc>)->Doc {
+    fold(docs, Doc::space)
+}
+
+pub fn vsep(docs:Vec<Doc>)->Doc {
+    fold(docs, Doc::line)
+}
+
+pub fn vcat(docs:Vec<Doc>)->Doc {
+    fold(docs, Doc::linebreak)
+}
+
+// All on one line separated by spaces if that fits, else one per line.
+pub fn sep(docs:Vec<Doc>)->Doc {
+    group(vsep(docs))
+}
+
+pub fn cat(docs:Vec<Doc>)->Doc {
+    group(vcat(docs))
+}
+
+// As many docs on each line as fit, separated by spaces, like words in a paragraph.
+pub fn fill(docs:Vec<Doc>)->Doc {
+    fold(docs, Doc::softline)
+}
+
+pub fn fill_cat(docs:Vec<Doc>)->Doc {
+    fold(docs, Doc::softbreak)
+}
+
+// p after every doc but the last.
+pub fn punctuate(p:Doc, docs:Vec<Doc>)->Vec<Doc> {
+    let last = docs.len().saturating_sub(1);
+    docs.into_iter().enumerate().map(|(i, d)| if i < last { d.append(p.clone()) } else { d }).collect()
+}
+
+// Wadler's bracket: flat as l x r, or else x on its own lines indented by 2 between them.
+pub fn bracket(l:&str, doc:Doc, r:&str)->Doc {
+    group(text(l).append(nest(2, linebreak().append(doc))).append(linebreak()).append(text(r)))
+}
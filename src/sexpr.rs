@@ -0,0 +1,134 @@
+use parsec::{State, Status, Parser, Parsec, Monad, parse as run};
+use parsec::atom::{eq, one_of, none_of, string, take_while1, eof, pack};
+use parsec::charset::{CharSet, Category};
+use parsec::span::{Span, located_by};
+use parsec::combinator::{either, try, many, many_till, between, skip, fix, Or};
+use std::fmt::{Formatter, Display};
+use std::fmt;
+use std::sync::Arc;
+
+// Every node keeps the char offsets it was read from, end exclusive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub expr: Expr,
+    pub start: usize,
+    pub end: usize,
+}
+
+// Quoted forms are read as lists, so 'x is (quote x) with the head spanning the quote mark.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Symbol(String),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    List(Vec<Node>),
+}
+
+const DELIMITERS: &'static str = "()[]\";'`,";
+
+pub fn parse(text:&str)->Status<Vec<Node>, usize> {
+    run(gap().then(many_till(node().over(gap()), eof())), text).into_result()
+}
+
+pub fn node()->Parser<char, Node, usize, usize> {
+    fix(|node| {
+        let round = try(eq('(')).over(gap()).then(many_till(node.clone().over(gap()), eq(')')));
+        let square = try(eq('[')).over(gap()).then(many_till(node.clone().over(gap()), eq(']')));
+        let list = map(either(round, square), Expr::List);
+        let mark = either(try(string(",@")).then(pack("unquote-splicing")), try(eq(',')).then(pack("unquote")))
+            .or(try(eq('\'')).then(pack("quote")))
+            .or(try(eq('`')).then(pack("quasiquote")));
+        let head = at(map(mark, |name:&'static str| Expr::Symbol(String::from(name))));
+        let quoted = head.over(gap()).bind(abc!(move |head:Node, state: &mut State<char, Index=usize, Tran=usize>|->Status<Expr, usize>{
+            node.parse(state).map(|item| Expr::List(vec![head.clone(), item]))
+        }));
+        at(either(list, quoted).or(text()).or(atom()))
+    })
+}
+
+fn text()->Parser<char, Expr, usize, usize> {
+    let escape = eq('\\').then(one_of("\"\\nrt")).bind(abc!(|c:char, _: &mut State<char, Index=usize, Tran=usize>|->Status<char, usize>{
+        Ok(match c {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            c => c,
+        })
+    }));
+    let chars = many(either(try(none_of(CharSet::chars("\"\\"))), escape));
+    map(between(try(eq('"')), eq('"'), chars), |chars:Vec<char>| Expr::String(chars.into_iter().collect()))
+}
+
+// Tokens that read as numbers become numbers, anything else is a symbol.
+fn atom()->Parser<char, Expr, usize, usize> {
+    map(take_while1(|c:&char| !c.is_whitespace() && !DELIMITERS.contains(*c)), |chars:Vec<char>| {
+        let token = chars.into_iter().collect::<String>();
+        if let Ok(x) = token.parse::<i64>() {
+            return Expr::Integer(x);
+        }
+        let digits = token.trim_start_matches(|c| c == '+' || c == '-');
+        let numeric = digits.chars().next().map_or(false, |c| c.is_digit(10))
+            || (digits.starts_with('.') && digits.chars().nth(1).map_or(false, |c| c.is_digit(10)));
+        match token.parse::<f64>() {
+            Ok(x) if numeric && token.len() - digits.len() <= 1 => Expr::Float(x),
+            _ => Expr::Symbol(token),
+        }
+    })
+}
+
+fn gap()->Parser<char, Vec<char>, usize, usize> {
+    let comment = eq(';').then(skip(none_of(CharSet::chars("\n")))).then(pack(' '));
+    skip(either(try(one_of(CharSet::category(Category::Whitespace))), comment))
+}
+
+fn at(p:Parser<char, Expr, usize, usize>)->Parser<char, Node, usize, usize> {
+    located_by(p, |expr, span:Span| Node{expr:expr, start:span.start, end:span.end})
+}
+
+fn map<R:Clone+'static, S:Clone+'static, F:'static>(p:Parser<char, R, usize, usize>, f:F)->Parser<char, S, usize, usize>
+where F:Fn(R)->S {
+    p.bind(abc!(move |x:R, _: &mut State<char, Index=usize, Tran=usize>|->Status<S, usize>{
+        Ok(f(x))
+    }))
+}
+
+impl Display for Node {
+    fn fmt(&self, formatter:&mut Formatter) -> Result<(), fmt::Error> {
+        self.expr.fmt(formatter)
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, formatter:&mut Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Expr::Symbol(ref name) => write!(formatter, "{}", name),
+            Expr::Integer(x) => write!(formatter, "{}", x),
+            Expr::Float(x) => write!(formatter, "{:?}", x),
+            Expr::String(ref data) => {
+                try!(write!(formatter, "\""));
+                for c in data.chars() {
+                    try!(match c {
+                        '"' => write!(formatter, "\\\""),
+                        '\\' => write!(formatter, "\\\\"),
+                        '\n' => write!(formatter, "\\n"),
+                        '\r' => write!(formatter, "\\r"),
+                        '\t' => write!(formatter, "\\t"),
+                        c => write!(formatter, "{}", c),
+                    });
+                }
+                write!(formatter, "\"")
+            }
+            Expr::List(ref items) => {
+                try!(write!(formatter, "("));
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        try!(write!(formatter, " "));
+                    }
+                    try!(item.fmt(formatter));
+                }
+                write!(formatter, ")")
+            }
+        }
+    }
+}
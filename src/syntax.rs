@@ -0,0 +1,305 @@
+use parsec::{State, Status, Parser, Parsec, Monad, parse_complete};
+use parsec::atom::{one, string, satisfy as satisfy_char, pack};
+use parsec::combinator::{either, try, many as many_of, skip};
+use parsec::text::{integer as integer_text, white_space};
+use std::cell::RefCell;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+// Invertible syntax descriptions after Rendel and Ostermann: one description is both the
+// parser and the printer of a language, so the two can not drift apart.
+//   let pair = text("(").then(integer()).over(text(",")).and(integer()).over(text(")"));
+//   pair.parse("(1,2)") is Ok((1, 2)) and pair.print(&(1, 2)) is Some("(1,2)")
+// Values get into shape through partial isomorphisms, applied after parsing and unapplied
+// before printing. print returns None for a value the description can not produce.
+pub type Printer<A> = Arc<Box<Fn(&A)->Option<String>>>;
+
+// A pair of partial functions, each the inverse of the other where both are defined.
+pub struct Iso<A, B> {
+    apply: Arc<Box<Fn(A)->Option<B>>>,
+    unapply: Arc<Box<Fn(B)->Option<A>>>,
+}
+
+impl<A, B> Clone for Iso<A, B> {
+    fn clone(&self)->Iso<A, B> {
+        Iso{apply:self.apply.clone(), unapply:self.unapply.clone()}
+    }
+}
+
+impl<A:'static, B:'static> Iso<A, B> {
+    pub fn new<F:'static, G:'static>(apply:F, unapply:G)->Iso<A, B>
+    where F:Fn(A)->Option<B>, G:Fn(B)->Option<A> {
+        Iso{apply:abc!(apply), unapply:abc!(unapply)}
+    }
+
+    pub fn apply(&self, a:A)->Option<B> {
+        (self.apply)(a)
+    }
+
+    pub fn unapply(&self, b:B)->Option<A> {
+        (self.unapply)(b)
+    }
+
+    pub fn inverse(&self)->Iso<B, A> {
+        Iso{apply:self.unapply.clone(), unapply:self.apply.clone()}
+    }
+
+    pub fn compose<C:'static>(&self, other:Iso<B, C>)->Iso<A, C> {
+        let (f, g) = (self.clone(), other.clone());
+        let (h, k) = (self.clone(), other);
+        Iso::new(move |a| f.apply(a).and_then(|b| g.apply(b)),
+                 move |c| k.unapply(c).and_then(|b| h.unapply(b)))
+    }
+}
+
+// The one value x, from and to nothing.
+pub fn element<A:PartialEq+Clone+'static>(x:A)->Iso<(), A> {
+    let y = x.clone();
+    Iso::new(move |_| Some(x.clone()), move |a| if a == y { Some(()) } else { None })
+}
+
+// The values pred holds for, e.g. names that are not keywords.
+pub fn subset<A:'static, F:'static>(pred:F)->Iso<A, A> where F:Fn(&A)->bool {
+    let pred = Arc::new(pred);
+    let back = pred.clone();
+    Iso::new(move |a| if pred(&a) { Some(a) } else { None }, move |a| if back(&a) { Some(a) } else { None })
+}
+
+pub fn chars()->Iso<Vec<char>, String> {
+    Iso::new(|x:Vec<char>| Some(x.into_iter().collect()), |x:String| Some(x.chars().collect()))
+}
+
+pub struct Syntax<A> {
+    parser: Parser<char, A, usize, usize>,
+    printer: Printer<A>,
+}
+
+impl<A> Clone for Syntax<A> {
+    fn clone(&self)->Syntax<A> {
+        Syntax{parser:self.parser.clone(), printer:self.printer.clone()}
+    }
+}
+
+impl<A:Clone+Debug+'static> Syntax<A> {
+    pub fn new(parser:Parser<char, A, usize, usize>, printer:Printer<A>)->Syntax<A> {
+        Syntax{parser:parser, printer:printer}
+    }
+
+    pub fn parser(&self)->Parser<char, A, usize, usize> {
+        self.parser.clone()
+    }
+
+    pub fn printer(&self)->Printer<A> {
+        self.printer.clone()
+    }
+
+    // Parses the whole text.
+    pub fn parse(&self, text:&str)->Status<A, usize> {
+        parse_complete(self.parser.clone(), text).into_result()
+    }
+
+    pub fn print(&self, value:&A)->Option<String> {
+        (self.printer)(value)
+    }
+
+    // iso <$> self
+    pub fn map<B:Clone+Debug+'static>(self, iso:Iso<A, B>)->Syntax<B> {
+        let back = iso.clone();
+        let printer = self.printer;
+        Syntax{
+            parser: self.parser.bind(abc!(move |a:A, state: &mut State<char, Index=usize, Tran=usize>|->Status<B, usize>{
+                let description = format!("unexpected {:?}", a);
+                iso.apply(a).ok_or_else(|| state.err(description))
+            })),
+            printer: abc!(move |b:&B| back.unapply(b.clone()).and_then(|a| printer(&a))),
+        }
+    }
+
+    // self <*> other
+    pub fn and<B:Clone+Debug+'static>(self, other:Syntax<B>)->Syntax<(A, B)> {
+        let (p, q) = (self.parser, other.parser);
+        let (f, g) = (self.printer, other.printer);
+        Syntax{
+            parser: abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<(A, B), usize>{
+                let a = try!(p.parse(state));
+                let b = try!(q.parse(state));
+                Ok((a, b))
+            }),
+            printer: abc!(move |x:&(A, B)| f(&x.0).and_then(|a| g(&x.1).map(|b| a + &b))),
+        }
+    }
+
+    // self <|> other, the first branch backtracks when it fails and the first printer that
+    // takes the value wins.
+    pub fn or(self, other:Syntax<A>)->Syntax<A> {
+        let (f, g) = (self.printer, other.printer);
+        Syntax{
+            parser: either(try(self.parser), other.parser),
+            printer: abc!(move |x:&A| f(x).or_else(|| g(x))),
+        }
+    }
+
+    // self <* other
+    pub fn over(self, other:Syntax<()>)->Syntax<A> {
+        self.and(other).map(Iso::new(|(a, _)| Some(a), |a| Some((a, ()))))
+    }
+
+    pub fn many(self)->Syntax<Vec<A>> {
+        let printer = self.printer;
+        Syntax{
+            parser: many_of(self.parser),
+            printer: abc!(move |xs:&Vec<A>| xs.iter().map(|x| printer(x)).collect::<Option<Vec<String>>>().map(|x| x.concat())),
+        }
+    }
+
+    pub fn many1(self)->Syntax<Vec<A>> {
+        self.many().map(subset(|xs:&Vec<A>| !xs.is_empty()))
+    }
+
+    pub fn sep_by(self, sep:Syntax<()>)->Syntax<Vec<A>> {
+        let rest = sep.then(self.clone()).many();
+        let first = self.and(rest).map(Iso::new(|(x, mut xs):(A, Vec<A>)| {
+            xs.insert(0, x);
+            Some(xs)
+        }, |mut xs:Vec<A>| if xs.is_empty() { None } else { Some((xs.remove(0), xs)) }));
+        first.or(Syntax{
+            parser: pack(Vec::new()),
+            printer: abc!(|xs:&Vec<A>| if xs.is_empty() { Some(String::new()) } else { None }),
+        })
+    }
+
+    pub fn between(self, open:Syntax<()>, close:Syntax<()>)->Syntax<A> {
+        open.then(self).over(close)
+    }
+
+    pub fn optional(self)->Syntax<Option<A>> {
+        let some = self.map(Iso::new(|a| Some(Some(a)), |a:Option<A>| a));
+        some.or(Syntax{
+            parser: pack(None),
+            printer: abc!(|a:&Option<A>| if a.is_none() { Some(String::new()) } else { None }),
+        })
+    }
+}
+
+impl Syntax<()> {
+    // self *> other
+    pub fn then<B:Clone+Debug+'static>(self, other:Syntax<B>)->Syntax<B> {
+        self.and(other).map(Iso::new(|(_, b)| Some(b), |b| Some(((), b))))
+    }
+}
+
+// Parses nothing into x and prints nothing for x, fails to print anything else.
+pub fn pure<A:PartialEq+Clone+Debug+'static>(x:A)->Syntax<A> {
+    let y = x.clone();
+    Syntax{
+        parser: pack(x),
+        printer: abc!(move |a:&A| if *a == y { Some(String::new()) } else { None }),
+    }
+}
+
+pub fn token()->Syntax<char> {
+    Syntax{
+        parser: one(),
+        printer: abc!(|c:&char| Some(c.to_string())),
+    }
+}
+
+pub fn satisfy<F:'static>(pred:F)->Syntax<char> where F:Fn(&char)->bool {
+    let pred = Arc::new(pred);
+    let back = pred.clone();
+    Syntax{
+        parser: satisfy_char(move |c:&char| pred(c)),
+        printer: abc!(move |c:&char| if back(c) { Some(c.to_string()) } else { None }),
+    }
+}
+
+pub fn text(literal:&str)->Syntax<()> {
+    let literal = String::from(literal);
+    Syntax{
+        parser: string(&literal).then(pack(())),
+        printer: abc!(move |_:&()| Some(literal.clone())),
+    }
+}
+
+pub fn integer()->Syntax<i64> {
+    let parser = integer_text().bind(abc!(|text:String, state: &mut State<char, Index=usize, Tran=usize>|->Status<i64, usize>{
+        text.parse::<i64>().map_err(|e| state.err(format!("invalid number {}: {}", text, e)))
+    }));
+    Syntax{parser:parser, printer:abc!(|x:&i64| Some(x.to_string()))}
+}
+
+// Any white space when parsing, none when printing.
+pub fn skip_space()->Syntax<()> {
+    Syntax{
+        parser: skip(white_space()).then(pack(())),
+        printer: abc!(|_:&()| Some(String::new())),
+    }
+}
+
+// Any white space when parsing, one space when printing.
+pub fn sep_space()->Syntax<()> {
+    Syntax{
+        parser: skip(white_space()).then(pack(())),
+        printer: abc!(|_:&()| Some(String::from(" "))),
+    }
+}
+
+// Builds the description on first use, so a rule can mention itself:
+//   fn expr()->Syntax<Expr> { ... lazy(expr) ... }
+pub fn lazy<A:Clone+Debug+'static, F:'static>(f:F)->Syntax<A> where F:Fn()->Syntax<A> {
+    let f = Arc::new(f);
+    let g = f.clone();
+    let parser:RefCell<Option<Parser<char, A, usize, usize>>> = RefCell::new(None);
+    let printer:RefCell<Option<Printer<A>>> = RefCell::new(None);
+    Syntax{
+        parser: abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<A, usize>{
+            let p = parser.borrow_mut().get_or_insert_with(|| f().parser).clone();
+            p.parse(state)
+        }),
+        printer: abc!(move |a:&A| {
+            let p = printer.borrow_mut().get_or_insert_with(|| g().printer).clone();
+            p(a)
+        }),
+    }
+}
+
+// Left associative operators, arg op arg op arg, folded through iso while parsing. Printing
+// unfolds the value as far as iso goes and prints what is left with arg.
+pub fn chainl1<A:Clone+Debug+'static, B:Clone+Debug+'static>(arg:Syntax<A>, op:Syntax<B>, iso:Iso<(A, (B, A)), A>)->Syntax<A> {
+    let first = arg.parser.clone();
+    let rest = many_of(try(op.clone().and(arg.clone()).parser));
+    let apply = iso.clone();
+    let (arg_printer, op_printer) = (arg.printer, op.printer);
+    Syntax{
+        parser: abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<A, usize>{
+            let mut re = try!(first.parse(state));
+            for (o, r) in try!(rest.parse(state)) {
+                let description = format!("unexpected {:?}", (&re, &o, &r));
+                re = try!(apply.apply((re, (o, r))).ok_or_else(|| state.err(description)));
+            }
+            Ok(re)
+        }),
+        printer: abc!(move |a:&A| {
+            let mut parts = Vec::new();
+            let mut left = a.clone();
+            while let Some((l, (o, r))) = iso.unapply(left.clone()) {
+                parts.push((o, r));
+                left = l;
+            }
+            let mut re = match arg_printer(&left) {
+                Some(x) => x,
+                None => return None,
+            };
+            for &(ref o, ref r) in parts.iter().rev() {
+                match (op_printer(o), arg_printer(r)) {
+                    (Some(o), Some(r)) => {
+                        re.push_str(&o);
+                        re.push_str(&r);
+                    }
+                    _ => return None,
+                }
+            }
+            Some(re)
+        }),
+    }
+}
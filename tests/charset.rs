@@ -0,0 +1,65 @@
+extern crate ruskell;
+use ruskell::parsec::{VecState, State, Parsec};
+use ruskell::parsec::atom::{one_of, none_of};
+use ruskell::parsec::charset::{CharSet, Category, Set};
+use ruskell::parsec::combinator::many;
+use std::iter::FromIterator;
+use std::error::Error;
+
+#[test]
+fn charset_test_0() {
+    let ident = CharSet::range('a'..='z').with_range('0'..='9').with_chars("_");
+    assert!(ident.contains(&'q'));
+    assert!(ident.contains(&'7'));
+    assert!(ident.contains(&'_'));
+    assert!(!ident.contains(&'Q'));
+    assert!(!ident.contains(&'é'));
+    assert_eq!(format!("{}", ident), "[a-z0-9_]");
+}
+
+#[test]
+fn charset_test_1() {
+    let set = CharSet::chars("-]").negate();
+    assert!(!set.contains(&'-'));
+    assert!(set.contains(&'a'));
+    assert!(set.contains(&'é'));
+    assert_eq!(format!("{}", set), "[^\\-\\]]");
+}
+
+#[test]
+fn charset_test_2() {
+    let letters = CharSet::category(Category::Alphabetic);
+    assert!(letters.contains(&'a'));
+    assert!(letters.contains(&'中'));
+    assert!(!letters.contains(&'1'));
+    let set = letters.union(CharSet::range('0'..='9').negate());
+    assert!(set.contains(&'中'));
+    assert!(set.contains(&'+'));
+    assert!(!set.contains(&'5'));
+    assert_eq!(format!("{}", set), "[[\\p{Alphabetic}][^0-9]]");
+}
+
+#[test]
+fn one_of_charset_test_0() {
+    let mut state = VecState::from_iter("ab12+".chars());
+    let re = many(one_of(CharSet::range('a'..='z').with_range('0'..='9'))).parse(&mut state);
+    assert_eq!(re.unwrap(), vec!['a', 'b', '1', '2']);
+    let re = one_of('a'..='z').parse(&mut state);
+    let msg = format!("{}", re.unwrap_err().description());
+    assert!(msg.contains("[a-z]"));
+}
+
+#[test]
+fn none_of_charset_test_0() {
+    let mut state = VecState::from_iter("x;".chars());
+    assert_eq!(none_of(";,").parse(&mut state).unwrap(), 'x');
+    assert!(none_of(";,").parse(&mut state).is_err());
+    assert_eq!(state.pos(), 2);
+}
+
+#[test]
+fn one_of_array_test_0() {
+    let mut state = VecState::from_iter(vec![3, 1, 4]);
+    assert_eq!(many(one_of(&[1, 2, 3])).parse(&mut state).unwrap(), vec![3, 1]);
+    assert_eq!(none_of(&[1, 2, 3]).parse(&mut state).unwrap(), 4);
+}
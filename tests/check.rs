@@ -0,0 +1,87 @@
+#[macro_use]
+extern crate ruskell;
+use ruskell::parsec::{State, Status, Monad, Parser};
+use ruskell::parsec::atom::eq;
+use ruskell::parsec::combinator::{between, sep_by};
+use ruskell::parsec::text::integer;
+use ruskell::parsec::check::{Rng, Arbitrary, Config, check, check_by, round_trip, assert_round_trip};
+use std::sync::Arc;
+
+fn list()->Parser<char, Vec<i64>, usize, usize> {
+    between(eq('['), eq(']'), sep_by(integer(), eq(',')))
+        .bind(abc!(|items:Vec<String>, _: &mut State<char, Index=usize, Tran=usize>|->Status<Vec<i64>, usize>{
+            Ok(items.iter().map(|x| x.parse::<i64>().unwrap()).collect())
+        }))
+}
+
+fn print(items:&Vec<i64>, sep:&str)->String {
+    format!("[{}]", items.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(sep))
+}
+
+#[test]
+fn rng_test_0() {
+    let mut x = Rng::new(42);
+    let mut y = Rng::new(42);
+    for _ in 0..100 {
+        assert_eq!(x.next_u64(), y.next_u64());
+    }
+    for _ in 0..100 {
+        let n = x.range(-3, 3);
+        assert!(n >= -3 && n <= 3);
+        assert!(x.below(7) < 7);
+        let u = x.unit();
+        assert!(u >= 0.0 && u < 1.0);
+    }
+    assert!(Rng::new(1).next_u64() != Rng::new(2).next_u64());
+}
+
+#[test]
+fn check_test_0() {
+    assert_eq!(check(&Config::new(), |x:&(i32, String)| {
+        if x.1.chars().count() <= 32 { Ok(()) } else { Err(format!("{:?} too long", x)) }
+    }).unwrap(), 100);
+
+    let failure = check(&Config::new(), |x:&Vec<i32>| {
+        if x.iter().all(|n| *n <= 5) { Ok(()) } else { Err(String::from("greater than 5")) }
+    }).unwrap_err();
+    assert_eq!(failure.shrunk, vec![6]);
+    assert_eq!(failure.message, "greater than 5");
+    assert!(failure.shrinks > 0);
+    assert!(failure.original.len() >= failure.shrunk.len());
+}
+
+#[test]
+fn check_test_1() {
+    // A panic fails the property as well.
+    let failure = check(&Config::new(), |x:&String| {
+        assert!(!x.contains('q'), "found q");
+        Ok(())
+    }).unwrap_err();
+    assert_eq!(failure.shrunk, "q");
+    assert_eq!(failure.message, "panic: found q");
+}
+
+#[test]
+fn round_trip_test_0() {
+    assert_round_trip(&Config::new(), list(), |x:&Vec<i64>| print(x, ","));
+    assert_eq!(round_trip(&list(), |x:&Vec<i64>| print(x, ","), &vec![-1, 20, 3]), Ok(()));
+}
+
+#[test]
+fn round_trip_test_1() {
+    let p = list();
+    let failure = check(&Config::new(), |x:&Vec<i64>| round_trip(&p, |x:&Vec<i64>| print(x, ", "), x)).unwrap_err();
+    assert_eq!(failure.shrunk, vec![0, 0]);
+    assert_eq!(failure.message, "[0, 0] printed as \"[0, 0]\" failed at 2: expect ] equal element , at 2");
+}
+
+#[test]
+fn check_by_test_0() {
+    // Only even numbers from the generator, shrinking keeps them even.
+    let config = Config{seed:7, cases:50, ..Config::new()};
+    let failure = check_by(&config, |rng:&mut Rng, size:usize| i64::arbitrary(rng, size * 10) * 2,
+                           |x:&i64| x.shrink().into_iter().map(|y| y / 2 * 2).filter(|y| y != x).collect(),
+                           |x:&i64| if x.abs() < 40 { Ok(()) } else { Err(format!("{}", x)) }).unwrap_err();
+    assert_eq!(failure.seed, 7);
+    assert_eq!(failure.shrunk.abs(), 40);
+}
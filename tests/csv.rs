@@ -0,0 +1,87 @@
+extern crate ruskell;
+use ruskell::csv::{Config, CsvError, Reader, read};
+use ruskell::parsec::State;
+use ruskell::parsec::stream::StreamState;
+
+fn row(fields:&[&str])->Vec<String> {
+    fields.iter().map(|f| String::from(*f)).collect()
+}
+
+#[test]
+fn csv_test_0() {
+    let re = read("a,b,c\r\n1,,3\n\"x, y\",\"say \"\"hi\"\"\",\"multi\nline\"\n", Config::csv()).unwrap();
+    assert_eq!(re, vec![
+        row(&["a", "b", "c"]),
+        row(&["1", "", "3"]),
+        row(&["x, y", "say \"hi\"", "multi\nline"]),
+    ]);
+}
+
+#[test]
+fn csv_test_1() {
+    let re = read("a\tb\n\"1\t2\"\t3", Config::tsv()).unwrap();
+    assert_eq!(re, vec![row(&["a", "b"]), row(&["1\t2", "3"])]);
+    let re = read("a;'it\\'s'", Config{delimiter:';', quote:'\'', escape:Some('\\'), header:false}).unwrap();
+    assert_eq!(re, vec![row(&["a", "it's"])]);
+}
+
+#[test]
+fn csv_header_test_0() {
+    let mut reader = Reader::new("name,age\nann,7\nbob,9\n".chars(), Config{header:true, ..Config::csv()}).unwrap();
+    assert_eq!(reader.header(), Some(&row(&["name", "age"])));
+    assert_eq!(reader.next(), Some(Ok(row(&["ann", "7"]))));
+    assert_eq!(reader.next(), Some(Ok(row(&["bob", "9"]))));
+    assert_eq!(reader.next(), None);
+}
+
+#[test]
+fn csv_error_test_0() {
+    let err = read("a,b\n1,\"2\"x,3\n", Config::csv()).unwrap_err();
+    assert_eq!(err, CsvError{line:2, column:6, message:String::from("expect delimiter or line end after closing quote")});
+    let err = read("a,b\nc,\"open\n", Config::csv()).unwrap_err();
+    assert_eq!((err.line, err.column), (2, 3));
+    assert_eq!(err.message, "unterminated quoted field");
+    let err = read("\"open", Config::csv()).unwrap_err();
+    assert_eq!((err.line, err.column), (1, 1));
+    let err = read("ab\"c", Config::csv()).unwrap_err();
+    assert_eq!((err.line, err.column, err.message.as_str()), (1, 3, "unexpected quote in field"));
+}
+
+#[test]
+fn csv_stream_test_0() {
+    let source = (0..10000).map(|i| format!("{},\"v{}\"\n", i, i)).flat_map(|line| line.chars().collect::<Vec<char>>());
+    let mut reader = Reader::new(source, Config::csv()).unwrap();
+    let mut count = 0;
+    let mut most = 0;
+    while let Some(record) = reader.next() {
+        let record = record.unwrap();
+        assert_eq!(record[1], format!("v{}", count));
+        most = most.max(reader.buffered());
+        count += 1;
+    }
+    assert_eq!(count, 10000);
+    assert!(most <= 2, "buffered {}", most);
+
+    // Lines dropped from the index still count toward the line of a later error.
+    let source = (0..5000).map(|i| format!("{}\n", i)).chain(Some(String::from("a\"b\n")))
+        .flat_map(|line| line.chars().collect::<Vec<char>>());
+    let err = Reader::new(source, Config::csv()).unwrap().last().unwrap().unwrap_err();
+    assert_eq!((err.line, err.column), (5001, 2));
+}
+
+#[test]
+fn stream_state_test_0() {
+    let mut state = StreamState::new("abcdef".chars());
+    assert_eq!(state.next(), Some('a'));
+    assert_eq!(state.buffered(), 0);
+    let tran = state.begin();
+    assert_eq!(state.next(), Some('b'));
+    assert_eq!(state.next(), Some('c'));
+    assert_eq!(state.buffered(), 2);
+    state.rollback(tran);
+    assert_eq!(state.pos(), 1);
+    assert_eq!(state.next(), Some('b'));
+    assert!(!state.seek_to(0));
+    assert!(state.seek_to(6));
+    assert_eq!(state.next(), None);
+}
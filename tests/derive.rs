@@ -0,0 +1,71 @@
+extern crate ruskell;
+#[macro_use]
+extern crate ruskell_derive;
+use ruskell::parsec::Error;
+use ruskell::parsec::derive::Parse;
+
+#[derive(Debug, Clone, PartialEq, Parse)]
+#[parse(prefix = "(", sep = ",", suffix = ")")]
+struct Point {
+    x: i64,
+    y: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Parse)]
+enum Color {
+    #[parse(literal = "rgb")]
+    Rgb(#[parse(before = "(")] u8, #[parse(before = ",")] u8, #[parse(before = ",", after = ")")] u8),
+    Red,
+    #[parse(literal = "grey")]
+    Grey,
+}
+
+#[derive(Debug, Clone, PartialEq, Parse)]
+struct Shape {
+    name: String,
+    #[parse(before = "at")]
+    origin: Point,
+    #[parse(before = "color")]
+    color: Option<Color>,
+    #[parse(before = "[", sep = ";", after = "]")]
+    points: Vec<Point>,
+}
+
+#[derive(Debug, Clone, PartialEq, Parse)]
+enum Tree {
+    #[parse(prefix = "(", suffix = ")")]
+    Node(Box<Tree>, Box<Tree>),
+    Leaf(u32),
+}
+
+#[test]
+fn derive_struct_test_0() {
+    assert_eq!(Point::parse_str("( -3 , 2.5 )").unwrap(), Point{x:-3, y:2.5});
+    let shape = Shape::parse_str("\"tri angle\" at (0, 0) color rgb(1, 2,3) [(1,0); (0,1)]").unwrap();
+    assert_eq!(shape, Shape{
+        name: String::from("tri angle"),
+        origin: Point{x:0, y:0.0},
+        color: Some(Color::Rgb(1, 2, 3)),
+        points: vec![Point{x:1, y:0.0}, Point{x:0, y:1.0}],
+    });
+    let shape = Shape::parse_str("dot at (1, 1) color []").unwrap();
+    assert_eq!(shape.color, None);
+    assert!(shape.points.is_empty());
+}
+
+#[test]
+fn derive_enum_test_0() {
+    assert_eq!(Color::parse_str("Red").unwrap(), Color::Red);
+    assert_eq!(Color::parse_str("grey").unwrap(), Color::Grey);
+    assert_eq!(Tree::parse_str("((1 2) 3)").unwrap(), Tree::Node(
+        Box::new(Tree::Node(Box::new(Tree::Leaf(1)), Box::new(Tree::Leaf(2)))),
+        Box::new(Tree::Leaf(3)),
+    ));
+}
+
+#[test]
+fn derive_error_test_0() {
+    assert_eq!(Point::parse_str("(1; 2)").unwrap_err().pos(), 2);
+    assert!(Color::parse_str("rgb(1, 2, 300)").is_err());
+    assert!(Color::parse_str("Blue").is_err());
+}
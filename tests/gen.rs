@@ -0,0 +1,87 @@
+extern crate ruskell;
+use ruskell::parsec::charset::CharSet;
+use ruskell::parsec::check::Rng;
+use ruskell::parsec::gen::{Gen, Generator, Config, literal, class, rule, seq, either, many1, between, sep_by};
+use ruskell::parsec::peg::Grammar;
+
+const LIST: &'static str = "
+list <- '[' (item (',' item)*)? ']'
+item <- [0-9] / list
+";
+
+fn list(config:Config)->Generator {
+    Generator::new(vec![
+        ("list", between(literal("["), literal("]"), sep_by(rule("item"), literal(",")))),
+        ("item", either(class(CharSet::range('0'..='9')), rule("list"))),
+    ], config).unwrap()
+}
+
+fn nesting(text:&str)->usize {
+    text.chars().fold((0, 0), |(depth, max), c| match c {
+        '[' => (depth + 1, if depth + 1 > max { depth + 1 } else { max }),
+        ']' => (depth - 1, max),
+        _ => (depth, max),
+    }).1
+}
+
+#[test]
+fn gen_test_0() {
+    let grammar = Grammar::new(LIST).unwrap();
+    let generator = list(Config{max_depth:4, ..Config::new()});
+    let mut rng = Rng::new(1);
+    let texts = (0..200).map(|_| generator.generate(&mut rng)).collect::<Vec<String>>();
+    for text in &texts {
+        assert!(grammar.parse(text).is_ok(), "{}", text);
+        assert!(nesting(text) <= 3, "{}", text);
+    }
+    assert!(texts.iter().any(|x| nesting(x) == 3));
+    assert!(texts.iter().any(|x| x.len() > 10));
+
+    let mut again = Rng::new(1);
+    assert_eq!(generator.generate(&mut again), texts[0]);
+    assert_eq!(generator.generate_rule("item", &mut again).map(|x| x.len() >= 1), Some(true));
+    assert_eq!(generator.generate_rule("value", &mut again), None);
+}
+
+#[test]
+fn gen_test_1() {
+    let err = |rules| Generator::new(rules, Config::new()).err().unwrap();
+    assert_eq!(err(vec![("a", seq(vec![literal("x"), rule("b")]))]), "undefined rule b");
+    assert_eq!(err(vec![("a", many1(seq(vec![literal("x"), rule("a")])))]), "rule a can not produce a finite text");
+    assert_eq!(err(vec![("a", class(CharSet::new()))]), "class [] has no member");
+    let repeat = Gen::Repeat(Box::new(literal("x")), 3, Some(2));
+    assert_eq!(err(vec![("a", seq(vec![literal("y"), repeat]))]), "repeat max 2 is below min 3");
+}
+
+#[test]
+fn gen_peg_test_0() {
+    let grammar = Grammar::new("
+        sum     <- product (sign product)*
+        product <- value (('*' / '/') value)*
+        value   <- number / '(' sum ')'
+        sign    <- [+\\-]
+        number  <- [0-9]+ ('.' [0-9]+)?
+    ").unwrap();
+    let generator = grammar.generator(Config::new()).unwrap();
+    let mut rng = Rng::new(99);
+    for _ in 0..100 {
+        let text = generator.generate(&mut rng);
+        assert!(grammar.parse(&text).is_ok(), "{}", text);
+    }
+}
+
+#[test]
+fn gen_peg_test_1() {
+    // Lookaheads generate nothing, generate drops the texts the grammar rejects.
+    let grammar = Grammar::new("
+        word    <- !keyword [a-i] [a-i]?
+        keyword <- ('if' / 'do') ![a-i]
+    ").unwrap();
+    let mut rng = Rng::new(5);
+    for _ in 0..50 {
+        let text = grammar.generate(&mut rng, Config::new(), 100).unwrap();
+        assert!(text != "if");
+    }
+    let never = Grammar::new("a <- !'x' 'x'").unwrap();
+    assert_eq!(never.generate(&mut rng, Config::new(), 10), Err(String::from("no valid text in 10 tries")));
+}
@@ -0,0 +1,41 @@
+extern crate ruskell;
+use ruskell::ini::{parse, Value};
+use ruskell::parsec::Error;
+
+#[test]
+fn ini_test_0() {
+    let text = "; global settings\nname = demo app\ndebug = true\n\n[server]\nhost = \"127.0.0.1\" # loopback\nport: 8080\nratio = -0.75\n\n[server.tls]\npaths = ['a.pem', \"b\\tc\"]\nversion = 1.2.3\n";
+    let re = parse(text).unwrap();
+    assert_eq!(re.get("name"), Some(&Value::String(String::from("demo app"))));
+    assert_eq!(re.get("debug"), Some(&Value::Bool(true)));
+    let server = re.get("server").unwrap();
+    assert_eq!(server.get("host"), Some(&Value::String(String::from("127.0.0.1"))));
+    assert_eq!(server.get("port"), Some(&Value::Integer(8080)));
+    assert_eq!(server.get("ratio"), Some(&Value::Float(-0.75)));
+    let tls = server.get("tls").unwrap();
+    assert_eq!(tls.get("paths"), Some(&Value::Array(vec![
+        Value::String(String::from("a.pem")),
+        Value::String(String::from("b\tc")),
+    ])));
+    assert_eq!(tls.get("version"), Some(&Value::String(String::from("1.2.3"))));
+}
+
+#[test]
+fn ini_array_test_0() {
+    let re = parse("matrix = [\n  [1, 2], # first\n  [3.5, false]\n]\r\nempty=[]").unwrap();
+    assert_eq!(re.get("matrix"), Some(&Value::Array(vec![
+        Value::Array(vec![Value::Integer(1), Value::Integer(2)]),
+        Value::Array(vec![Value::Float(3.5), Value::Bool(false)]),
+    ])));
+    assert_eq!(re.get("empty"), Some(&Value::Array(vec![])));
+}
+
+#[test]
+fn ini_error_test_0() {
+    assert_eq!(parse("a = \"open\nb = 1").unwrap_err().pos(), 9);
+    assert_eq!(parse("a = [1, 2\n").unwrap_err().pos(), 10);
+    assert_eq!(parse("a = 1\na = 2").unwrap_err().pos(), 6);
+    assert_eq!(parse("a = 1\n[a]\n").unwrap_err().pos(), 6);
+    assert!(parse("[section\n").is_err());
+    assert!(parse("key without value\n").is_err());
+}
@@ -0,0 +1,98 @@
+extern crate ruskell;
+use ruskell::json::{parse, Value};
+use ruskell::parsec::Error;
+use std::collections::BTreeMap;
+
+#[path = "../benches/json/handwritten.rs"]
+mod handwritten;
+
+use handwritten::{Handwritten, sample};
+
+// Conformance corpus, in the spirit of JSONTestSuite: y_ must parse, n_ must be rejected.
+const ACCEPT: &'static [&'static str] = &[
+    "null", "true", "false", "0", "-0", "1", "-1", "123", "1.5", "-1.5e10", "1E+2", "1e-2", "0.0",
+    "\"\"", "\"abc\"", "\" \\\" \\\\ \\/ \\b \\f \\n \\r \\t \"", "\"\\u0041\"", "\"\\ud83d\\ude00\"",
+    "\"中文\"", "[]", "[ ]", "[1]", "[1,2,3]", "[[[]]]", "[null, true, false, \"x\", 1.0, {}]",
+    "{}", "{ }", "{\"a\":1}", "{\"a\" : [1, {\"b\" : null}] , \"c\" : \"d\"}",
+    " \t\r\n [ 1 , 2 ] \t\r\n ", "{\"\":0}", "1e400", "[-0.0e-0]",
+];
+
+const REJECT: &'static [&'static str] = &[
+    "", " ", "nul", "True", "NULL", "01", "-", "+1", "1.", ".1", "1e", "1e+", "0x10", "1 2",
+    "\"abc", "abc\"", "'abc'", "\"\\x\"", "\"\\u12\"", "\"\\u12G4\"", "\"a\u{1}b\"", "\"\t\"",
+    "[", "]", "[1,]", "[,1]", "[1 2]", "[1,,2]", "{", "{\"a\"}", "{\"a\":}", "{\"a\":1,}",
+    "{a:1}", "{1:1}", "{\"a\" 1}", "[1]]", "{}}", "[\"a\",]", "nulll", "truefalse",
+];
+
+#[test]
+fn json_accept_test() {
+    for doc in ACCEPT {
+        let re = parse(doc);
+        assert!(re.is_ok(), "expect {:?} accepted, got {:?}", doc, re);
+    }
+}
+
+#[test]
+fn json_reject_test() {
+    for doc in REJECT {
+        let re = parse(doc);
+        assert!(re.is_err(), "expect {:?} rejected, got {:?}", doc, re);
+    }
+}
+
+#[test]
+fn json_value_test_0() {
+    let re = parse("{\"name\": \"ruskell\", \"tags\": [\"parsec\", 1, -2.5e1, true, null], \"empty\": {}}").unwrap();
+    let mut check = BTreeMap::new();
+    check.insert(String::from("name"), Value::String(String::from("ruskell")));
+    check.insert(String::from("tags"), Value::Array(vec![
+        Value::String(String::from("parsec")),
+        Value::Number(1.0),
+        Value::Number(-25.0),
+        Value::Bool(true),
+        Value::Null,
+    ]));
+    check.insert(String::from("empty"), Value::Object(BTreeMap::new()));
+    assert_eq!(re, Value::Object(check));
+}
+
+#[test]
+fn json_value_test_1() {
+    let re = parse("\"a\\n\\u00e9\\ud83d\\ude00\"").unwrap();
+    assert_eq!(re, Value::String(String::from("a\né😀")));
+    let re = parse("{\"a\":{\"b\":[7]}}").unwrap();
+    assert_eq!(re.get("a").and_then(|a| a.get("b")), Some(&Value::Array(vec![Value::Number(7.0)])));
+}
+
+#[test]
+fn json_nested_test_0() {
+    let depth = 64;
+    let doc = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    let mut re = parse(&doc).unwrap();
+    for _ in 1..depth {
+        re = match re {
+            Value::Array(mut items) => items.pop().unwrap(),
+            _ => panic!("expect array"),
+        };
+    }
+    assert_eq!(re, Value::Array(vec![]));
+}
+
+#[test]
+fn json_error_test_0() {
+    let re = parse("[1, 2, tru]");
+    let err = re.unwrap_err();
+    assert_eq!(err.pos(), 7);
+    assert_eq!(format!("{}", err), "expect value");
+    let err = parse("{\"a\": [1,]}").unwrap_err();
+    assert_eq!((err.pos(), format!("{}", err)), (9, String::from("expect value")));
+}
+
+#[test]
+fn handwritten_agrees_test() {
+    let doc = sample();
+    assert_eq!(parse(&doc).ok(), Handwritten::parse(&doc));
+    for doc in ACCEPT.iter().chain(REJECT) {
+        assert_eq!(parse(doc).ok(), Handwritten::parse(doc), "{:?}", doc);
+    }
+}
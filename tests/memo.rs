@@ -0,0 +1,138 @@
+#[macro_use]
+extern crate ruskell;
+use ruskell::parsec::{VecState, State, Status, Parsec, Monad, Parser};
+use ruskell::parsec::atom::{eq, eof, one, satisfy};
+use ruskell::parsec::combinator::{try, either, many1, memo, left_rec, fix, Or};
+use std::sync::Arc;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::iter::FromIterator;
+
+fn counted(calls:Rc<Cell<usize>>)->Parser<char, Vec<char>, usize, usize> {
+    let digits = many1(one());
+    abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<Vec<char>, usize>{
+        calls.set(calls.get() + 1);
+        digits.parse(state)
+    })
+}
+
+#[test]
+fn memo_test_0() {
+    let calls = Rc::new(Cell::new(0));
+    let body = memo(counted(calls.clone()));
+    let rule = either(try(body.clone().over(eq('x'))), try(body.clone().over(eq('y')))).or(body.clone());
+    let mut state = VecState::from_iter("abc".chars());
+    let re = rule(&mut state);
+    assert_eq!(re.unwrap(), vec!['a', 'b', 'c']);
+    assert_eq!(calls.get(), 1);
+    assert_eq!(state.pos(), 3);
+}
+
+#[test]
+fn memo_test_1() {
+    let calls = Rc::new(Cell::new(0));
+    let plain = counted(calls.clone());
+    let rule = either(try(plain.clone().over(eq('x'))), plain.clone());
+    let mut state = VecState::from_iter("abc".chars());
+    assert!(rule(&mut state).is_ok());
+    assert_eq!(calls.get(), 2);
+}
+
+#[test]
+fn memo_rollback_test_0() {
+    let a = memo(eq('a'));
+    let mut state = VecState::from_iter("ab".chars());
+    let tran = state.begin();
+    assert_eq!(a(&mut state).unwrap(), 'a');
+    state.rollback(tran);
+    assert_eq!(state.pos(), 0);
+    assert_eq!(a(&mut state).unwrap(), 'a');
+    assert_eq!(state.pos(), 1);
+    assert!(a(&mut state).is_err());
+    assert_eq!(state.pos(), 2);
+    state.seek_to(1);
+    assert!(a(&mut state).is_err());
+    assert_eq!(state.pos(), 2);
+}
+
+fn number()->Parser<char, i64, usize, usize> {
+    abc!(|state: &mut State<char, Index=usize, Tran=usize>|->Status<i64, usize>{
+        let digits = try!(many1(satisfy(|x:&char| x.is_digit(10)))(state));
+        Ok(digits.into_iter().collect::<String>().parse::<i64>().unwrap())
+    })
+}
+
+// expr := expr '-' number | number
+fn expr()->Parser<char, i64, usize, usize> {
+    let cell:Rc<RefCell<Option<Parser<char, i64, usize, usize>>>> = Rc::new(RefCell::new(None));
+    let inner = cell.clone();
+    let this:Parser<char, i64, usize, usize> = abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<i64, usize>{
+        let p = inner.borrow().clone().unwrap();
+        p(state)
+    });
+    let minus = this.bind(abc!(|x:i64, state: &mut State<char, Index=usize, Tran=usize>|->Status<i64, usize>{
+        try!(eq('-')(state));
+        number()(state).map(|y| x - y)
+    }));
+    let rule = left_rec(either(try(minus), number()));
+    *cell.borrow_mut() = Some(rule.clone());
+    rule
+}
+
+#[test]
+fn left_rec_test_0() {
+    let mut state = VecState::from_iter("10-3-2".chars());
+    let re = expr().over(eof())(&mut state);
+    assert_eq!(re.unwrap(), 5);
+}
+
+#[test]
+fn left_rec_test_1() {
+    let mut state = VecState::from_iter("42-".chars());
+    let re = expr()(&mut state);
+    assert_eq!(re.unwrap(), 42);
+    assert_eq!(state.pos(), 2);
+    let mut state = VecState::from_iter("-".chars());
+    assert!(expr()(&mut state).is_err());
+}
+
+#[test]
+fn left_rec_fix_test_0() {
+    // expr := expr '-' number | number
+    let expr = fix(|expr: Parser<char, i64, usize, usize>| {
+        let minus = expr.bind(abc!(|x:i64, state: &mut State<char, Index=usize, Tran=usize>|->Status<i64, usize>{
+            try!(eq('-')(state));
+            number()(state).map(|y| x - y)
+        }));
+        left_rec(either(try(minus), number()))
+    });
+    let mut state = VecState::from_iter("20-5-4-1".chars());
+    assert_eq!(expr.over(eof())(&mut state).unwrap(), 10);
+}
+
+fn counted_number(calls:Rc<Cell<usize>>)->Parser<char, i64, usize, usize> {
+    let p = number();
+    abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<i64, usize>{
+        calls.set(calls.get() + 1);
+        p(state)
+    })
+}
+
+#[test]
+fn left_rec_memo_test_0() {
+    // expr := expr '-' number | number, with the recursion running through a memo rule. Only
+    // that rule skips the table while expr grows, number is parsed once at each position.
+    let calls = Rc::new(Cell::new(0));
+    let num = memo(counted_number(calls.clone()));
+    let expr = fix(move |expr: Parser<char, i64, usize, usize>| {
+        let n = num.clone();
+        let minus = memo(expr.bind(abc!(move |x:i64, state: &mut State<char, Index=usize, Tran=usize>|->Status<i64, usize>{
+            try!(eq('-')(state));
+            n(state).map(|y| x - y)
+        })));
+        left_rec(either(try(minus), num.clone()))
+    });
+    let mut state = VecState::from_iter("20-5-4-1".chars());
+    assert_eq!(expr.over(eof())(&mut state).unwrap(), 10);
+    assert_eq!(calls.get(), 4);
+}
@@ -0,0 +1,22 @@
+// No #[macro_use]: parse_do! is called by its path, as crates on the 2018 edition do.
+extern crate ruskell;
+use ruskell::parsec::{VecState, Parsec};
+use ruskell::parsec::atom::{eq, one_of};
+use std::iter::FromIterator;
+
+#[test]
+fn parse_do_path_test_0() {
+    let pair = ruskell::parse_do!{
+        x <- one_of("0123456789");
+        _ <- eq(',');
+        mut y <- one_of("0123456789");
+        let x = x.to_digit(10).unwrap();
+        eq('!');
+        ret { y.make_ascii_uppercase(); (x, y) }
+    };
+    let mut state = VecState::from_iter("1,2!".chars());
+    assert_eq!(pair.parse(&mut state).unwrap(), (1, '2'));
+    let last = ruskell::parse_do!{ eq('a'); eq('b') };
+    let mut state = VecState::from_iter("ab".chars());
+    assert_eq!(last.parse(&mut state).unwrap(), 'b');
+}
@@ -1,8 +1,10 @@
 #[macro_use]
 extern crate ruskell;
 use ruskell::parsec::{VecState, State, Status, Parsec, Monad, Parser};
-use ruskell::parsec::atom::{one, eq, eof, one_of, none_of, ne};
-use ruskell::parsec::combinator::{try, either, many, many1, between, many_till, Either, Or};
+use ruskell::parsec::atom::{one, eq, pack, eof, one_of, none_of, ne, tag, string, string_ci,
+                            satisfy, take_while, take_while1, take_till, take_while_m_n};
+use ruskell::parsec::combinator::{try, either, many, many1, between, many_till, sep_by, sep_by1, lazy, fix, look_ahead,
+                                  not_followed_by, skip, Either, Or};
 use std::sync::Arc;
 use std::iter::FromIterator;
 use std::error::Error;
@@ -308,6 +310,31 @@ fn many1_test_2() {
     assert_eq!(data, ver);
 }
 
+#[test]
+fn many_progress_test_0() {
+    // A round that consumes nothing ends the repetition instead of looping forever.
+    let mut state = VecState::from_iter("aab".chars());
+    let optional = either(try(eq('a')), pack('-'));
+    assert_eq!(many(optional.clone()).parse(&mut state).unwrap(), vec!['a', 'a', '-']);
+    assert_eq!(state.pos(), 2);
+    assert_eq!(many(look_ahead(one())).parse(&mut state).unwrap(), vec!['b']);
+    assert_eq!(many1(optional.clone()).parse(&mut state).unwrap(), vec!['-']);
+    assert_eq!(skip(optional).parse(&mut state).unwrap(), vec![]);
+    assert_eq!(state.pos(), 2);
+}
+
+#[test]
+fn sep_by_progress_test_0() {
+    let mut state = VecState::from_iter("abc".chars());
+    assert_eq!(sep_by(pack('x'), pack(',')).parse(&mut state).unwrap(), vec!['x', 'x']);
+    assert_eq!(sep_by1(pack('x'), look_ahead(one())).parse(&mut state).unwrap(), vec!['x', 'x']);
+    assert_eq!(state.pos(), 0);
+    let err = many_till(pack('x'), eq(';')).parse(&mut state).unwrap_err();
+    assert_eq!(err.description(), "many_till item consumed nothing");
+    let mut state = VecState::from_iter(";".chars());
+    assert_eq!(many_till(pack('x'), eq(';')).parse(&mut state).unwrap(), vec![]);
+}
+
 #[test]
 fn between_test_0() {
     let mut state = VecState::from_iter("\"xxxxxxxx\".".chars());
@@ -367,3 +394,221 @@ fn many_till_test_1() {
     let ver = "This is a string.".chars().into_iter().collect::<Vec<char>>();
     assert_eq!(data, ver);
 }
+
+#[test]
+fn tag_test_0() {
+    let mut state = VecState::from_iter(vec![1, 2, 3, 4]);
+    let re = tag(&[1, 2, 3])(&mut state);
+    assert!(re.is_ok());
+    assert_eq!(re.unwrap(), vec![1, 2, 3]);
+    assert_eq!(state.pos(), 3);
+}
+
+#[test]
+fn tag_test_1() {
+    let mut state = VecState::from_iter(vec![1, 2, 4]);
+    let re = tag(&[1, 2, 3])(&mut state);
+    assert!(re.is_err());
+    assert_eq!(state.pos(), 0);
+}
+
+#[test]
+fn string_test_0() {
+    let mut state = VecState::from_iter("letter".chars());
+    let keyword = either(string("lex"), string("let"));
+    let re = keyword(&mut state);
+    assert!(re.is_ok());
+    assert_eq!(re.unwrap(), "let");
+    assert_eq!(state.pos(), 3);
+}
+
+#[test]
+fn string_test_1() {
+    let mut state = VecState::from_iter("select".chars());
+    let re: Status<String, usize> = string("selection")(&mut state);
+    assert!(re.is_err());
+    assert_eq!(state.pos(), 0);
+    let msg = format!("{}", re.unwrap_err().description());
+    assert!(msg.contains("\"selection\""));
+}
+
+#[test]
+fn string_ci_test_0() {
+    let mut state = VecState::from_iter("SeLeCt *".chars());
+    let re = string_ci("select").over(eq(' '))(&mut state);
+    assert!(re.is_ok());
+    assert_eq!(re.unwrap(), "SeLeCt");
+}
+
+#[test]
+fn satisfy_test_0() {
+    let mut state = VecState::from_iter("a1".chars());
+    let re = satisfy(|x:&char| x.is_numeric())(&mut state);
+    assert!(re.is_err());
+    assert_eq!(state.pos(), 0);
+    let re = satisfy(|x:&char| x.is_alphabetic())(&mut state);
+    assert_eq!(re.unwrap(), 'a');
+}
+
+#[test]
+fn take_while_test_0() {
+    let mut state = VecState::from_iter(vec![1, 3, 5, 6, 7]);
+    let re = take_while(|x:&i32| x % 2 == 1)(&mut state);
+    assert_eq!(re.unwrap(), vec![1, 3, 5]);
+    assert_eq!(state.pos(), 3);
+    let re = take_while(|x:&i32| x % 2 == 1)(&mut state);
+    assert_eq!(re.unwrap(), vec![]);
+    assert_eq!(state.pos(), 3);
+}
+
+#[test]
+fn take_while1_test_0() {
+    let mut state = VecState::from_iter("abc".chars());
+    let re = take_while1(|x:&char| x.is_numeric())(&mut state);
+    assert!(re.is_err());
+    assert_eq!(state.pos(), 0);
+}
+
+#[test]
+fn take_till_test_0() {
+    let mut state = VecState::from_iter("key=value".chars());
+    let re = take_till(|x:&char| *x == '=').over(eq('='))(&mut state);
+    assert_eq!(re.unwrap(), vec!['k', 'e', 'y']);
+    let re = take_till(|x:&char| *x == '=')(&mut state);
+    assert_eq!(re.unwrap().into_iter().collect::<String>(), "value");
+}
+
+#[test]
+fn take_while_m_n_test_0() {
+    let mut state = VecState::from_iter("12345".chars());
+    let re = take_while_m_n(2, 4, |x:&char| x.is_numeric())(&mut state);
+    assert_eq!(re.unwrap(), vec!['1', '2', '3', '4']);
+    let re = take_while_m_n(2, 4, |x:&char| x.is_numeric())(&mut state);
+    assert!(re.is_err());
+    assert_eq!(state.pos(), 4);
+}
+
+fn nested()->Parser<char, usize, usize, usize> {
+    fix(|nested| {
+        let inner = many(between(eq('('), eq(')'), nested));
+        abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<usize, usize>{
+            inner(state).map(|depths| depths.into_iter().map(|d| d + 1).max().unwrap_or(0))
+        })
+    })
+}
+
+#[test]
+fn fix_test_0() {
+    let mut state = VecState::from_iter("(()(()))()".chars());
+    let re = nested().over(eof())(&mut state);
+    assert_eq!(re.unwrap(), 3);
+}
+
+#[test]
+fn fix_test_1() {
+    let mut state = VecState::from_iter("(()".chars());
+    let re = nested().over(eof())(&mut state);
+    assert!(re.is_err());
+}
+
+// list := '[' (digit | list)* ']'
+fn list()->Parser<char, String, usize, usize> {
+    let item = either(try(satisfy(|x:&char| x.is_digit(10)).bind(abc!(|x:char, _: &mut State<char, Index=usize, Tran=usize>|->Status<String, usize>{
+        Ok(x.to_string())
+    }))), lazy(list));
+    abc!(move |state: &mut State<char, Index=usize, Tran=usize>|->Status<String, usize>{
+        let items = try!(between(eq('['), eq(']'), many(item.clone()))(state));
+        Ok(format!("({})", items.concat()))
+    })
+}
+
+#[test]
+fn lazy_test_0() {
+    let mut state = VecState::from_iter("[1[2[]3]4]".chars());
+    let re = list().over(eof())(&mut state);
+    assert_eq!(re.unwrap(), "(1(2()3)4)");
+}
+
+#[test]
+fn sep_by_test_0() {
+    let mut state = VecState::from_iter("a,b,c;".chars());
+    let re = sep_by(one(), eq(','))(&mut state);
+    assert_eq!(re.unwrap(), vec!['a', 'b', 'c']);
+    assert_eq!(state.pos(), 5);
+}
+
+#[test]
+fn sep_by_test_1() {
+    let mut state = VecState::from_iter("a,b,".chars());
+    let re = sep_by(ne(','), eq(','))(&mut state);
+    assert_eq!(re.unwrap(), vec!['a', 'b']);
+    assert_eq!(state.pos(), 3);
+    let mut state = VecState::from_iter(",".chars());
+    let re = sep_by(ne(','), eq(','))(&mut state);
+    assert_eq!(re.unwrap(), vec![]);
+    assert_eq!(state.pos(), 0);
+}
+
+#[test]
+fn sep_by1_test_0() {
+    let mut state = VecState::from_iter("a;b;c".chars());
+    let re = sep_by1(ne(';'), eq(';'))(&mut state);
+    assert_eq!(re.unwrap(), vec!['a', 'b', 'c']);
+    assert_eq!(state.pos(), 5);
+    let mut state = VecState::from_iter("a;;".chars());
+    let re = sep_by1(ne(';'), eq(';'))(&mut state);
+    assert_eq!(re.unwrap(), vec!['a']);
+    assert_eq!(state.pos(), 1);
+    let mut state = VecState::from_iter(";a".chars());
+    assert!(sep_by1(ne(';'), eq(';'))(&mut state).is_err());
+}
+
+#[test]
+fn parse_do_test_0() {
+    let mut state = VecState::from_iter("1,2!".chars());
+    let pair = parse_do!{
+        x <- one_of("0123456789");
+        _ <- eq(',');
+        let x = x.to_digit(10).unwrap();
+        y <- one_of("0123456789");
+        ret (x, y)
+    };
+    assert_eq!(pair.parse(&mut state).unwrap(), (1, '2'));
+    assert_eq!(state.pos(), 3);
+    let bang = parse_do!{ eq('?'); eq('!') };
+    assert!(bang.parse(&mut state).is_err());
+    assert_eq!(state.pos(), 4);
+}
+
+#[test]
+fn parse_do_test_1() {
+    // A length prefixed field: the count read first decides the parser that follows.
+    let field = parse_do!{
+        n <- one();
+        _ <- eq(':');
+        let n = (n as u8 - b'0') as usize;
+        mut data <- take_while_m_n(n, n, |_:&char| true);
+        ret { data.reverse(); data }
+    };
+    let mut state = VecState::from_iter("3:abcde".chars());
+    assert_eq!(field.parse(&mut state).unwrap(), vec!['c', 'b', 'a']);
+    assert_eq!(state.pos(), 5);
+    let mut state = VecState::from_iter("4:ab".chars());
+    assert!(field.parse(&mut state).is_err());
+}
+
+#[test]
+fn look_ahead_test_0() {
+    let mut state = VecState::from_iter("ab".chars());
+    assert_eq!(look_ahead(tag(&['a', 'b'])).parse(&mut state).unwrap(), vec!['a', 'b']);
+    assert_eq!(state.pos(), 0);
+    assert!(look_ahead(eq('b')).parse(&mut state).is_err());
+    assert_eq!(state.pos(), 0);
+    let keyword = string("if").over(not_followed_by(satisfy(|c:&char| c.is_alphanumeric())));
+    assert!(keyword.parse(&mut state).is_err());
+    let mut state = VecState::from_iter("if x".chars());
+    assert_eq!(keyword.parse(&mut state).unwrap(), "if");
+    assert_eq!(state.pos(), 2);
+    assert!(not_followed_by(eq(' ')).parse(&mut state).is_err());
+    assert_eq!(state.pos(), 2);
+}
@@ -0,0 +1,201 @@
+#[macro_use]
+extern crate ruskell;
+#[macro_use]
+extern crate ruskell_derive;
+use ruskell::parsec::{VecState, Parsec, Parser, Error};
+use ruskell::parsec::atom::eq;
+use ruskell::parsec::combinator::many1;
+use ruskell::parsec::peg::{Grammar, Tree};
+use ruskell::parsec::span::Span;
+use std::error::Error as StdError;
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+const ARITHMETIC: &'static str = r#"
+# sums of products, left to right
+sum     <- product (sign product)*
+product <- value (('*' / "/") value)*
+value   <- number / '(' sum ')'
+sign    <- [+\-]
+number  <- [0-9]+ ('.' [0-9]+)?
+"#;
+
+fn eval(tree:&Tree)->f64 {
+    match tree.rule.as_str() {
+        "number" => tree.text.parse().unwrap(),
+        "value" => eval(&tree.children[0]),
+        "product" => {
+            let values = tree.children.iter().map(eval).collect::<Vec<f64>>();
+            let ops = tree.text.chars().filter(|c| *c == '*' || *c == '/').collect::<Vec<char>>();
+            ops.iter().zip(&values[1..]).fold(values[0], |acc, (op, x)| if *op == '*' { acc * x } else { acc / x })
+        }
+        "sum" => {
+            let mut re = eval(&tree.children[0]);
+            for pair in tree.children[1..].chunks(2) {
+                let x = eval(&pair[1]);
+                re = if pair[0].text == "+" { re + x } else { re - x };
+            }
+            re
+        }
+        _ => panic!("unexpected rule {}", tree.rule),
+    }
+}
+
+#[test]
+fn peg_test_0() {
+    let grammar = Grammar::new(ARITHMETIC).unwrap();
+    assert_eq!(grammar.names(), &["sum", "product", "value", "sign", "number"]);
+    let tree = grammar.parse("2*(3+4)-10/4").unwrap();
+    assert_eq!(tree.rule, "sum");
+    assert_eq!(tree.span, Span::new(0, 12));
+    assert_eq!(eval(&tree), 11.5);
+    assert_eq!(tree.children_of("sign").count(), 1);
+    assert!(grammar.parse("2*(3+4").is_err());
+    assert_eq!(grammar.parse("1+").unwrap_err().pos(), 1);
+}
+
+#[test]
+fn peg_lookahead_test_0() {
+    let grammar = Grammar::new("
+        word    <- !keyword [a-zA-Z_] [a-zA-Z0-9_]*
+        keyword <- ('if' / 'else') ![a-zA-Z0-9_]
+        call    <- word &'(' .
+        other   <- [^a-z]+ .?
+    ").unwrap();
+    assert_eq!(grammar.parse("iffy").unwrap().text, "iffy");
+    assert!(grammar.parse("if").is_err());
+    let mut state = VecState::from_iter("f(x)".chars());
+    let call = grammar.rule("call").unwrap().parse(&mut state).unwrap();
+    assert_eq!(call.text, "f(");
+    assert_eq!(call.children[0].rule, "word");
+    let mut state = VecState::from_iter("ABC-d".chars());
+    assert_eq!(grammar.rule("other").unwrap().parse(&mut state).unwrap().text, "ABC-d");
+}
+
+#[test]
+fn peg_error_test_0() {
+    let err = Grammar::new("a <- b 'x'").err().unwrap();
+    assert_eq!(err.pos(), 5);
+    let err = Grammar::new("a <- 'x'\nb <- 'y'\na <- 'z'").err().unwrap();
+    assert_eq!(err.pos(), 18);
+    assert!(Grammar::new("a <- ('x'").is_err());
+    assert!(Grammar::new("a <- [x").is_err());
+    assert!(Grammar::new("").is_err());
+}
+
+#[test]
+fn peg_repeat_test_0() {
+    // A round that matches nothing ends the repetition instead of looping.
+    let grammar = Grammar::new("a <- ('x'?)* 'y'\nb <- (&'x')+ .").unwrap();
+    assert_eq!(grammar.parse("xxy").unwrap().text, "xxy");
+    assert_eq!(grammar.parse("y").unwrap().text, "y");
+    assert!(grammar.parse("xz").is_err());
+    let mut state = VecState::from_iter("xxz".chars());
+    assert_eq!(grammar.rule("b").unwrap().parse(&mut state).unwrap().text, "x");
+}
+
+#[test]
+fn peg_left_recursion_test_0() {
+    let err = Grammar::new("a <- a 'x' / 'x'").err().unwrap();
+    assert_eq!(err.pos(), 0);
+    assert_eq!(err.description(), "rule a is left recursive");
+    // Through other rules and after parts that may match nothing.
+    let err = Grammar::new("s <- 'x'\na <- b? c\nb <- 'y'\nc <- &'z' a").err().unwrap();
+    assert_eq!(err.description(), "rule a is left recursive");
+    assert_eq!(err.pos(), 9);
+    assert!(Grammar::new("a <- 'x' a / 'x'").is_ok());
+}
+
+peg! {
+    mod arithmetic r#"
+        # the same grammar as ARITHMETIC, compiled ahead of time
+        sum     <- product (sign product)*
+        product <- value (('*' / "/") value)*
+        value   <- number / '(' sum ')'
+        sign    <- [+\-]
+        number  <- [0-9]+ ('.' [0-9]+)?
+    "#
+}
+
+// Rules the grammar does not define are hand-written parsers in the enclosing module.
+fn bangs()->Parser<char, Vec<char>, usize, usize> {
+    many1(eq('!'))
+}
+
+peg! {
+    mod shout r#"
+        shout <- word bangs
+        word  <- !'if' [a-z]+
+    "#
+}
+
+#[test]
+fn peg_macro_test_0() {
+    let runtime = Grammar::new(ARITHMETIC).unwrap();
+    for text in &["1", "2*(3+4)-10/4", "((1.5))", "1+2+3*4/5"] {
+        assert_eq!(arithmetic::parse(text).unwrap(), runtime.parse(text).unwrap());
+    }
+    assert_eq!(eval(&arithmetic::parse("2*(3+4)-10/4").unwrap()), 11.5);
+    assert!(arithmetic::parse("2*(3+4").is_err());
+    let sum:Parser<char, Tree, usize, usize> = abc!(arithmetic::sum);
+    let mut state = VecState::from_iter("1+2)".chars());
+    assert_eq!(sum.parse(&mut state).unwrap().text, "1+2");
+}
+
+#[test]
+fn peg_macro_test_1() {
+    let tree = shout::parse("hey!!").unwrap();
+    assert_eq!(tree.text, "hey!!");
+    assert_eq!(tree.children.len(), 1);
+    assert_eq!(tree.children[0].rule, "word");
+    assert!(shout::parse("if!").is_err());
+    assert!(shout::parse("hey").is_err());
+}
+
+peg! {
+    mod repeat r#"
+        a <- ('x'?)* 'y'
+        b <- (&'x')+ .
+    "#
+}
+
+#[test]
+fn peg_macro_test_2() {
+    // The macro and the runtime agree on repetitions that match nothing and on rule names.
+    let runtime = Grammar::new("a <- ('x'?)* 'y'\nb <- (&'x')+ .").unwrap();
+    for text in &["xxy", "y"] {
+        assert_eq!(repeat::parse(text).unwrap(), runtime.parse(text).unwrap());
+    }
+    assert!(repeat::parse("xz").is_err());
+    let mut state = VecState::from_iter("xxz".chars());
+    assert_eq!(repeat::b(&mut state).unwrap().text, "x");
+    assert!(Grammar::new("a-b <- 'x'").is_err());
+}
+
+// Rule names that are also names inside the generated code.
+peg! {
+    mod clash r#"
+        parse    <- text (state / Ok)* re
+        text     <- start children
+        start    <- [a-z]
+        children <- mark? tran?
+        mark     <- '!'
+        tran     <- '?'
+        state    <- before ','
+        before   <- [0-9]
+        Ok       <- ';'
+        re       <- '.'
+    "#
+}
+
+#[test]
+fn peg_macro_test_3() {
+    let tree = clash::parse("a!?1,;.").unwrap();
+    assert_eq!(tree.rule, "parse");
+    assert_eq!(tree.text, "a!?1,;.");
+    assert_eq!(tree.children.iter().map(|t| t.rule.as_str()).collect::<Vec<_>>(), vec!["text", "state", "Ok", "re"]);
+    let mut state = VecState::from_iter("b.".chars());
+    assert_eq!(clash::rules::parse(&mut state).unwrap().text, "b.");
+    let mut state = VecState::from_iter("x".chars());
+    assert_eq!(clash::start(&mut state).unwrap().text, "x");
+}
@@ -0,0 +1,8 @@
+extern crate trybuild;
+
+// Grammars peg! must turn down at compile time, with the messages in tests/ui/*.stderr.
+#[test]
+fn peg_ui_test_0() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/peg_*.rs");
+}
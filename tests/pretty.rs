@@ -0,0 +1,96 @@
+extern crate ruskell;
+use ruskell::pretty::{Doc, nil, text, string, line, nest, group, hcat, hsep, vsep, vcat, sep, fill, punctuate, bracket};
+use ruskell::sexpr::{parse, Node, Expr};
+
+// The tree from Wadler's paper.
+struct Tree(&'static str, Vec<Tree>);
+
+fn tree()->Tree {
+    Tree("aaa", vec![
+        Tree("bbbbb", vec![Tree("ccc", vec![]), Tree("dd", vec![])]),
+        Tree("eee", vec![]),
+        Tree("ffff", vec![Tree("gg", vec![]), Tree("hhh", vec![]), Tree("ii", vec![])]),
+    ])
+}
+
+fn show_tree(t:&Tree)->Doc {
+    let brackets = if t.1.is_empty() {
+        nil()
+    } else {
+        text("[") + nest(1, show_trees(&t.1)) + text("]")
+    };
+    group(text(t.0) + nest(t.0.len(), brackets))
+}
+
+fn show_trees(ts:&[Tree])->Doc {
+    let docs = ts.iter().map(show_tree).collect();
+    vsep(punctuate(text(","), docs))
+}
+
+fn show_tree_bracket(t:&Tree)->Doc {
+    if t.1.is_empty() {
+        return text(t.0);
+    }
+    let docs = t.1.iter().map(show_tree_bracket).collect();
+    text(t.0) + bracket("[", vsep(punctuate(text(","), docs)), "]")
+}
+
+#[test]
+fn pretty_tree_test_0() {
+    assert_eq!(show_tree(&tree()).pretty(80), "aaa[bbbbb[ccc, dd], eee, ffff[gg, hhh, ii]]");
+    assert_eq!(show_tree(&tree()).pretty(30), "aaa[bbbbb[ccc, dd],\n    eee,\n    ffff[gg, hhh, ii]]");
+    assert_eq!(show_tree(&tree()).pretty(12), "aaa[bbbbb[ccc,\n          dd],\n    eee,\n    ffff[gg,\n         hhh,\n         ii]]");
+}
+
+#[test]
+fn pretty_tree_test_1() {
+    assert_eq!(show_tree_bracket(&tree()).pretty(20),
+               "aaa[\n  bbbbb[ccc, dd],\n  eee,\n  ffff[gg, hhh, ii]\n]");
+    assert_eq!(format!("{:17}", show_tree_bracket(&tree())),
+               "aaa[\n  bbbbb[ccc, dd],\n  eee,\n  ffff[\n    gg,\n    hhh,\n    ii\n  ]\n]");
+    assert_eq!(show_tree_bracket(&tree()).to_string(), "aaa[bbbbb[ccc, dd], eee, ffff[gg, hhh, ii]]");
+}
+
+#[test]
+fn pretty_test_0() {
+    let words = "the quick brown fox jumps over the lazy dog".split(' ').map(text).collect::<Vec<Doc>>();
+    assert_eq!(fill(words.clone()).pretty(15), "the quick brown\nfox jumps over\nthe lazy dog");
+    assert_eq!(sep(words.clone()).pretty(80), "the quick brown fox jumps over the lazy dog");
+    assert_eq!(sep(words[..3].to_vec()).pretty(10), "the\nquick\nbrown");
+    assert_eq!(hsep(words[..3].to_vec()).pretty(1), "the quick brown");
+    assert_eq!(vcat(words[..3].to_vec()).pretty(80), "the\nquick\nbrown");
+
+    // A hardline keeps its group broken, and empty lines get no indentation.
+    let block = text("do") + nest(2, line() + string("a\n\nb")) + line() + text("end");
+    assert_eq!(group(block).pretty(80), "do\n  a\n\n  b\nend");
+}
+
+fn sexpr(node:&Node)->Doc {
+    match node.expr {
+        Expr::List(ref items) if !items.is_empty() => {
+            let mut docs = items.iter().map(sexpr).collect::<Vec<Doc>>();
+            let head = docs.remove(0);
+            group(text("(") + nest(1, vsep(Some(head).into_iter().chain(docs).collect())) + text(")"))
+        }
+        _ => text(&node.expr.to_string()),
+    }
+}
+
+#[test]
+fn pretty_sexpr_test_0() {
+    // Parse with the reader, print back at two widths.
+    let nodes = parse("(define (square x)\n    (* x x))").unwrap();
+    assert_eq!(sexpr(&nodes[0]).pretty(80), "(define (square x) (* x x))");
+    assert_eq!(sexpr(&nodes[0]).pretty(20), "(define\n (square x)\n (* x x))");
+    assert_eq!(parse(&sexpr(&nodes[0]).pretty(20)).unwrap()[0].to_string(), nodes[0].to_string());
+}
+
+#[test]
+fn pretty_large_test_0() {
+    // A chain as long as the list, printed and dropped without deep recursion.
+    let doc = hcat((0..100000).map(|_| text("a")).collect());
+    assert_eq!(doc.pretty(80).len(), 100000);
+    let shared = doc.clone();
+    drop(doc);
+    drop(group(shared));
+}
@@ -0,0 +1,49 @@
+extern crate ruskell;
+use ruskell::parsec::{VecState, Parsec, Monad};
+use ruskell::parsec::atom::{eq, take_while1};
+use ruskell::parsec::combinator::{try, either, many, Or};
+use ruskell::parsec::profile::ProfileState;
+use ruskell::parsec::trace::{TraceState, trace};
+use std::iter::FromIterator;
+
+#[test]
+fn profile_test_0() {
+    let digits = trace("digits", take_while1(|c:&char| c.is_digit(10)));
+    let plus = trace("plus", try(digits.clone().then(eq('+')).then(digits.clone())));
+    let minus = trace("minus", try(digits.clone().then(eq('-')).then(digits.clone())));
+    let expr = trace("expr", either(plus, minus).or(digits.clone()));
+    let mut state = ProfileState::new(VecState::from_iter("12*3".chars()));
+    assert_eq!(expr.parse(&mut state).unwrap(), vec!['1', '2']);
+
+    let profile = state.profile();
+    let digits = profile.get("digits").unwrap();
+    assert_eq!(digits.calls, 3);
+    assert_eq!(digits.failures, 0);
+    // Stopping at '*' reads nothing, so it is no backtrack.
+    assert_eq!(digits.backtracks, 0);
+    let plus = profile.get("plus").unwrap();
+    assert_eq!(plus.calls, 1);
+    assert_eq!(plus.failures, 1);
+    assert_eq!(plus.backtracks, 1);
+    assert_eq!(profile.get("minus").unwrap().backtracks, 1);
+    assert_eq!(profile.get("expr").unwrap().calls, 1);
+
+    // Times depend on the machine, so the report is checked by its counts.
+    let mut report = profile.report();
+    report.sort_by(|x, y| y.calls.cmp(&x.calls).then(x.rule.cmp(&y.rule)));
+    let calls = report.iter().map(|s| (s.rule.as_str(), s.calls, s.backtracks)).collect::<Vec<_>>();
+    assert_eq!(calls, vec![("digits", 3, 0), ("expr", 1, 0), ("minus", 1, 1), ("plus", 1, 1)]);
+    let text = format!("{}", profile);
+    assert!(text.starts_with("rule"));
+    assert_eq!(text.lines().count(), 5);
+}
+
+#[test]
+fn profile_trace_test_0() {
+    let a = trace("a", many(eq('a')));
+    let mut state = ProfileState::new(TraceState::new(VecState::from_iter("aab".chars())));
+    assert_eq!(a.parse(&mut state).unwrap(), vec!['a', 'a']);
+    assert_eq!(state.profile().get("a").unwrap().calls, 1);
+    let state = state.into_inner();
+    assert_eq!(state.log().events().len(), 2);
+}
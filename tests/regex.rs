@@ -0,0 +1,60 @@
+extern crate ruskell;
+use ruskell::parsec::{VecState, State, Parsec, Monad};
+use ruskell::parsec::atom::eq;
+use ruskell::parsec::regex::{Regex, regex};
+use ruskell::parsec::text::StringState;
+use std::iter::FromIterator;
+use std::error::Error;
+
+#[test]
+fn regex_test_0() {
+    let mut state = StringState::new(String::from("2016-08-21T10:30:00Z rest"));
+    let re = regex(r"\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}(Z|[+-]\d{2}:\d{2})").parse(&mut state);
+    assert_eq!(re.unwrap(), "2016-08-21T10:30:00Z");
+    assert_eq!(state.pos(), 20);
+}
+
+#[test]
+fn regex_test_1() {
+    let mut state = VecState::from_iter("123e4567-e89b-12d3-a456-426614174000".chars());
+    let uuid = regex("[0-9a-fA-F]{8}(-[0-9a-fA-F]{4}){3}-[0-9a-fA-F]{12}");
+    assert_eq!(uuid.parse(&mut state).unwrap(), "123e4567-e89b-12d3-a456-426614174000");
+}
+
+#[test]
+fn regex_test_2() {
+    let mut state = StringState::new(String::from("192.168.0.1:80"));
+    let ip = regex(r"(\d{1,3}\.){3}\d{1,3}").over(eq(':'));
+    assert_eq!(ip.parse(&mut state).unwrap(), "192.168.0.1");
+}
+
+#[test]
+fn regex_test_3() {
+    let mut state = StringState::new(String::from("abc"));
+    let re = regex(r"a+\d").parse(&mut state);
+    assert_eq!(state.pos(), 0);
+    let msg = format!("{}", re.unwrap_err().description());
+    assert!(msg.contains("/a+\\d/"));
+}
+
+#[test]
+fn regex_test_4() {
+    let mut state = StringState::new(String::from("forest"));
+    assert_eq!(regex("for|forest|fo").parse(&mut state).unwrap(), "forest");
+    let mut state = StringState::new(String::from("aaa"));
+    assert_eq!(regex("(a*)*b?").parse(&mut state).unwrap(), "aaa");
+    let mut state = StringState::new(String::from("xyz"));
+    assert_eq!(regex("[^a-c]*").parse(&mut state).unwrap(), "xyz");
+}
+
+// clippy takes this Regex for the regex crate's and rejects the bad patterns on purpose here.
+#[test]
+#[allow(clippy::invalid_regex)]
+fn regex_new_test_0() {
+    assert!(Regex::new("a(b").is_err());
+    assert!(Regex::new("a)b").is_err());
+    assert!(Regex::new("*a").is_err());
+    assert!(Regex::new("[a-").is_err());
+    assert!(Regex::new("a{3,2}").is_err());
+    assert!(Regex::new(r"\q").is_err());
+}
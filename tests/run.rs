@@ -0,0 +1,61 @@
+extern crate ruskell;
+use ruskell::parsec::{VecState, Monad, parse, parse_complete};
+use ruskell::parsec::atom::{eq, one_of, string};
+use ruskell::parsec::combinator::many1;
+use std::iter::FromIterator;
+use std::error::Error;
+
+#[test]
+fn parse_test_0() {
+    let re = parse(string("let"), "let x");
+    assert!(re.is_ok());
+    assert_eq!(re.value, Some(String::from("let")));
+    assert_eq!(re.pos, 3);
+    assert_eq!(re.rest, vec![' ', 'x']);
+    assert!(re.error.is_none());
+}
+
+#[test]
+fn parse_test_1() {
+    let digits = many1(one_of(&[1, 2, 3]));
+    let re = parse(digits.clone(), vec![1, 2, 4]);
+    assert_eq!(re.into_result().unwrap(), vec![1, 2]);
+    let re = parse(digits.clone(), &[3, 3][..]);
+    assert_eq!(re.value, Some(vec![3, 3]));
+    assert!(re.rest.is_empty());
+    let re = parse(digits.clone(), vec![7, 8].into_iter());
+    assert!(!re.is_ok());
+    assert_eq!(re.value, None);
+    assert_eq!(re.rest, vec![8]);
+}
+
+#[test]
+fn parse_test_2() {
+    let re = parse(eq('a'), VecState::from_iter("ab".chars()));
+    assert_eq!(re.value, Some('a'));
+    let re = parse(eq('a'), "ab".chars());
+    assert_eq!(re.rest, vec!['b']);
+    let re = parse(eq('a'), String::from("ab"));
+    assert_eq!(re.pos, 1);
+}
+
+#[test]
+fn parse_iterator_test_0() {
+    let re = parse(many1(one_of(&[2, 4])), (1..4).map(|x| x * 2));
+    assert_eq!(re.value, Some(vec![2, 4]));
+    assert_eq!(re.rest, vec![6]);
+    let re = parse_complete(string("ab"), "a b".chars().filter(|c| !c.is_whitespace()));
+    assert_eq!(re.into_result().unwrap(), "ab");
+}
+
+#[test]
+fn parse_complete_test_0() {
+    let re = parse_complete(string("let").over(eq(' ')), "let x");
+    assert!(!re.is_ok());
+    assert_eq!(re.pos, 4);
+    assert_eq!(re.rest, vec!['x']);
+    let msg = format!("{}", re.error.unwrap().description());
+    assert!(msg.contains("eof"));
+    let re = parse_complete(string("let"), "let");
+    assert_eq!(re.into_result().unwrap(), "let");
+}
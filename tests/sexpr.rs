@@ -0,0 +1,52 @@
+extern crate ruskell;
+use ruskell::sexpr::{parse, Node, Expr};
+use ruskell::parsec::Error;
+
+fn symbol(name:&str, start:usize, end:usize)->Node {
+    Node{expr:Expr::Symbol(String::from(name)), start:start, end:end}
+}
+
+#[test]
+fn sexpr_test_0() {
+    let re = parse("(define (sq x) [* x x]) ; square\n'(1 -2.5 \"a\\\"b\" +) `(a ,b ,@c)").unwrap();
+    assert_eq!(re.len(), 3);
+    assert_eq!(re[0].to_string(), "(define (sq x) (* x x))");
+    assert_eq!(re[1].to_string(), "(quote (1 -2.5 \"a\\\"b\" +))");
+    assert_eq!(re[2].to_string(), "(quasiquote (a (unquote b) (unquote-splicing c)))");
+    match re[1].expr {
+        Expr::List(ref items) => match items[1].expr {
+            Expr::List(ref items) => {
+                assert_eq!(items[0].expr, Expr::Integer(1));
+                assert_eq!(items[1].expr, Expr::Float(-2.5));
+                assert_eq!(items[2].expr, Expr::String(String::from("a\"b")));
+                assert_eq!(items[3], symbol("+", 49, 50));
+            }
+            _ => panic!("expect a list"),
+        },
+        _ => panic!("expect a list"),
+    }
+}
+
+#[test]
+fn sexpr_span_test_0() {
+    let re = parse("  (a (b c))  ").unwrap();
+    assert_eq!((re[0].start, re[0].end), (2, 11));
+    match re[0].expr {
+        Expr::List(ref items) => {
+            assert_eq!(items[0], symbol("a", 3, 4));
+            assert_eq!((items[1].start, items[1].end), (5, 10));
+        }
+        _ => panic!("expect a list"),
+    }
+    let re = parse("' x").unwrap();
+    assert_eq!((re[0].start, re[0].end), (0, 3));
+    assert_eq!(re[0].expr, Expr::List(vec![symbol("quote", 0, 1), symbol("x", 2, 3)]));
+}
+
+#[test]
+fn sexpr_error_test_0() {
+    assert_eq!(parse("(a b").unwrap_err().pos(), 4);
+    assert_eq!(parse("(a]").unwrap_err().pos(), 2);
+    assert!(parse(")").is_err());
+    assert!(parse("'").is_err());
+}
@@ -0,0 +1,54 @@
+extern crate ruskell;
+use ruskell::parsec::{VecState, Parsec, Monad, Parser};
+use ruskell::parsec::atom::{eq, one_of};
+use ruskell::parsec::combinator::{many, many1, sep_by};
+use ruskell::parsec::span::{Span, Located, spanned, located, located_by, span_of};
+use ruskell::parsec::text::digit;
+use std::iter::FromIterator;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Ast {
+    Number(u32, Span),
+    Sum(Vec<Ast>, Span),
+}
+
+fn number()->Parser<char, Ast, usize, usize> {
+    located_by(many1(digit()), |digits:Vec<char>, span| {
+        Ast::Number(digits.into_iter().collect::<String>().parse().unwrap(), span)
+    })
+}
+
+#[test]
+fn spanned_test_0() {
+    let mut state = VecState::from_iter("  abc".chars());
+    let p = many(eq(' ')).then(spanned(many1(one_of("abc"))));
+    assert_eq!(p.parse(&mut state).unwrap(), (vec!['a', 'b', 'c'], Span::new(2, 5)));
+    let mut state = VecState::from_iter("xyz".chars());
+    assert!(spanned(eq('a')).parse(&mut state).is_err());
+}
+
+#[test]
+fn located_test_0() {
+    let mut state = VecState::from_iter("12+3+456".chars());
+    let sum = located_by(sep_by(number(), eq('+')), |items, span| Ast::Sum(items, span));
+    assert_eq!(sum.parse(&mut state).unwrap(), Ast::Sum(vec![
+        Ast::Number(12, Span::new(0, 2)),
+        Ast::Number(3, Span::new(3, 4)),
+        Ast::Number(456, Span::new(5, 8)),
+    ], Span::new(0, 8)));
+    let mut state = VecState::from_iter("ab".chars());
+    let re = located(eq('a')).parse(&mut state).unwrap();
+    assert_eq!(re, Located{value:'a', span:Span::new(0, 1)});
+    assert_eq!(re.map(|c| c.to_string()).value, "a");
+    assert_eq!(span_of(many(eq('x'))).parse(&mut state).unwrap(), Span::new(1, 1));
+}
+
+#[test]
+fn span_test_0() {
+    let x = Span::new(3, 5);
+    let y = Span::new(1, 4);
+    assert_eq!(x.join(&y), Span::new(1, 5));
+    assert!(x.contains(&3) && !x.contains(&5));
+    assert!(Span::new(2, 2).is_empty());
+    assert_eq!(x.to_string(), "3..5");
+}
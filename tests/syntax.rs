@@ -0,0 +1,105 @@
+extern crate ruskell;
+use ruskell::syntax::{Syntax, Iso, text, integer, satisfy, skip_space, sep_space, lazy, chainl1, element, subset, chars};
+use ruskell::parsec::check::{Config, check, round_trip};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Num(i64),
+    Var(String),
+    Add(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+}
+
+fn num()->Iso<i64, Expr> {
+    Iso::new(|n| Some(Expr::Num(n)), |e| match e {
+        Expr::Num(n) => Some(n),
+        _ => None,
+    })
+}
+
+fn var()->Iso<String, Expr> {
+    Iso::new(|name| Some(Expr::Var(name)), |e| match e {
+        Expr::Var(name) => Some(name),
+        _ => None,
+    })
+}
+
+fn binary(op:char)->Iso<(Expr, (char, Expr)), Expr> {
+    Iso::new(move |(l, (o, r)):(Expr, (char, Expr))| match o {
+        '+' if op == '+' => Some(Expr::Add(Box::new(l), Box::new(r))),
+        '*' if op == '*' => Some(Expr::Mul(Box::new(l), Box::new(r))),
+        _ => None,
+    }, move |e| match e {
+        Expr::Add(l, r) if op == '+' => Some((*l, ('+', *r))),
+        Expr::Mul(l, r) if op == '*' => Some((*l, ('*', *r))),
+        _ => None,
+    })
+}
+
+fn operator(op:char)->Syntax<char> {
+    skip_space().then(satisfy(move |c:&char| *c == op)).over(skip_space())
+}
+
+fn expr()->Syntax<Expr> {
+    let name = satisfy(|c:&char| c.is_alphabetic()).many1().map(chars()).map(var());
+    let factor = integer().map(num()).or(name).or(lazy(expr).between(text("("), text(")")));
+    let term = chainl1(factor, operator('*'), binary('*'));
+    chainl1(term, operator('+'), binary('+'))
+}
+
+fn list()->Syntax<Vec<i64>> {
+    integer().sep_by(text(",").then(sep_space())).between(text("["), text("]"))
+}
+
+#[test]
+fn syntax_test_0() {
+    let pair = text("(").then(integer()).over(text(",")).and(integer()).over(text(")"));
+    assert_eq!(pair.parse("(1,-2)").unwrap(), (1, -2));
+    assert_eq!(pair.print(&(1, -2)), Some(String::from("(1,-2)")));
+    assert!(pair.parse("(1;2)").is_err());
+}
+
+#[test]
+fn syntax_expr_test_0() {
+    let e = expr().parse("1 + 2 * (x+3) * y").unwrap();
+    assert_eq!(e, Expr::Add(
+        Box::new(Expr::Num(1)),
+        Box::new(Expr::Mul(
+            Box::new(Expr::Mul(
+                Box::new(Expr::Num(2)),
+                Box::new(Expr::Add(Box::new(Expr::Var(String::from("x"))), Box::new(Expr::Num(3)))))),
+            Box::new(Expr::Var(String::from("y")))))));
+    // Spaces are dropped and parentheses come back only where the tree needs them.
+    assert_eq!(expr().print(&e), Some(String::from("1+2*(x+3)*y")));
+    assert_eq!(expr().parse(&expr().print(&e).unwrap()).unwrap(), e);
+
+    let right = Expr::Add(Box::new(Expr::Num(1)), Box::new(Expr::Add(Box::new(Expr::Num(2)), Box::new(Expr::Num(3)))));
+    assert_eq!(expr().print(&right), Some(String::from("1+(2+3)")));
+}
+
+#[test]
+fn syntax_list_test_0() {
+    assert_eq!(list().parse("[1,2 ,3]").is_err(), true);
+    assert_eq!(list().parse("[1,2,  3]").unwrap(), vec![1, 2, 3]);
+    assert_eq!(list().parse("[]").unwrap(), vec![]);
+    assert_eq!(list().print(&vec![1, 2, 3]), Some(String::from("[1, 2, 3]")));
+    assert_eq!(list().print(&vec![]), Some(String::from("[]")));
+
+    let p = list().parser();
+    assert_eq!(check(&Config::new(), |x:&Vec<i64>| round_trip(&p, |x:&Vec<i64>| list().print(x).unwrap(), x)).unwrap(), 100);
+}
+
+#[test]
+fn syntax_iso_test_0() {
+    let yes = text("yes").map(element(true)).or(text("no").map(element(false)));
+    assert_eq!(yes.parse("no").unwrap(), false);
+    assert_eq!(yes.print(&true), Some(String::from("yes")));
+
+    // Printing fails for values outside the description.
+    let small = integer().map(subset(|n:&i64| *n < 10));
+    assert!(small.parse("12").is_err());
+    assert_eq!(small.print(&12), None);
+    assert_eq!(small.map(num()).optional().print(&Some(Expr::Num(3))), Some(String::from("3")));
+    assert_eq!(num().compose(var().inverse()).apply(1), None);
+    assert_eq!(num().inverse().unapply(5), Some(Expr::Num(5)));
+}
@@ -0,0 +1,41 @@
+extern crate ruskell;
+use ruskell::parsec::{State, Parsec, Monad};
+use ruskell::parsec::atom::eof;
+use ruskell::parsec::text::{StringState, digit, alpha, newline, integer, float};
+use ruskell::parsec::combinator::many;
+
+#[test]
+fn string_state_test_0() {
+    let mut state = StringState::new(String::from("aé1"));
+    assert_eq!(state.next(), Some('a'));
+    assert_eq!(state.next(), Some('é'));
+    assert_eq!(state.pos(), 3);
+    assert_eq!(state.next(), Some('1'));
+    assert_eq!(state.next(), None);
+}
+
+#[test]
+fn digit_alpha_test_0() {
+    let mut state = StringState::new(String::from("a1"));
+    assert!(digit().parse(&mut state).is_err());
+    assert_eq!(state.pos(), 0);
+    assert_eq!(alpha().parse(&mut state).unwrap(), 'a');
+    assert_eq!(digit().parse(&mut state).unwrap(), '1');
+}
+
+#[test]
+fn newline_test_0() {
+    let mut state = StringState::new(String::from("\r\n\n\r"));
+    let re = many(newline()).over(eof()).parse(&mut state);
+    assert_eq!(re.unwrap(), vec!["\r\n", "\n", "\r"]);
+}
+
+#[test]
+fn number_test_0() {
+    let mut state = StringState::new(String::from("-42"));
+    assert_eq!(integer().parse(&mut state).unwrap(), "-42");
+    let mut state = StringState::new(String::from("-.5"));
+    assert_eq!(float().parse(&mut state).unwrap(), "-0.5");
+    let mut state = StringState::new(String::from("3.14"));
+    assert_eq!(float().parse(&mut state).unwrap(), "3.14");
+}
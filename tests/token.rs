@@ -0,0 +1,126 @@
+#[macro_use]
+extern crate ruskell;
+use ruskell::parsec::{VecState, State, Status, Parsec, Monad, Parser, Error};
+use ruskell::parsec::atom::{eq, one_of, satisfy};
+use ruskell::parsec::combinator::{either, try, many, many1, sep_by1, skip, Or};
+use ruskell::parsec::span::{Span, spanned};
+use ruskell::parsec::text::{digit, alpha};
+use ruskell::parsec::token::{TokenState, TokenPos};
+use std::error::Error as StdError;
+use std::fmt;
+use std::sync::Arc;
+use std::iter::FromIterator;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Tok {
+    Num(String),
+    Name(String),
+    Op(char),
+}
+
+impl fmt::Display for Tok {
+    fn fmt(&self, formatter:&mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Tok::Num(ref x) | Tok::Name(ref x) => write!(formatter, "{}", x),
+            Tok::Op(c) => write!(formatter, "{}", c),
+        }
+    }
+}
+
+fn lex(text:&str)->Vec<(Tok, Span)> {
+    let num = many1(digit()).bind(abc!(|x:Vec<char>, _: &mut State<char, Index=usize, Tran=usize>|->Status<Tok, usize>{
+        Ok(Tok::Num(x.into_iter().collect()))
+    }));
+    let name = many1(alpha()).bind(abc!(|x:Vec<char>, _: &mut State<char, Index=usize, Tran=usize>|->Status<Tok, usize>{
+        Ok(Tok::Name(x.into_iter().collect()))
+    }));
+    let op = one_of("+*()").bind(abc!(|c:char, _: &mut State<char, Index=usize, Tran=usize>|->Status<Tok, usize>{
+        Ok(Tok::Op(c))
+    }));
+    let token: Parser<char, Tok, usize, usize> = either(num, name).or(op);
+    let lexer = skip(eq(' ')).then(many(spanned(token).over(skip(eq(' ')))));
+    lexer.parse(&mut VecState::from_iter(text.chars())).unwrap()
+}
+
+fn operand()->Parser<Tok, Tok, TokenPos, usize> {
+    satisfy(|t:&Tok| match *t { Tok::Num(_) | Tok::Name(_) => true, _ => false })
+}
+
+#[test]
+fn token_state_test_0() {
+    let tokens = lex(" x + 12*(y+3) ");
+    assert_eq!(tokens.len(), 9);
+    assert_eq!(tokens[2], (Tok::Num(String::from("12")), Span::new(5, 7)));
+    let mut state = TokenState::new(tokens);
+    let start = state.pos();
+    assert_eq!(start.span(), Span::new(1, 2));
+    let p = sep_by1(operand(), try(eq(Tok::Op('+'))));
+    assert_eq!(p.parse(&mut state).unwrap(), vec![Tok::Name(String::from("x")), Tok::Num(String::from("12"))]);
+    assert_eq!(state.pos().span(), Span::new(7, 8));
+    assert_eq!(state.pos().offset(), 3);
+    assert_eq!(state.offset(), 3);
+    while state.next().is_some() {}
+    let end = state.pos();
+    assert_eq!(end.span(), Span::new(13, 13));
+    assert!(state.seek_to(start));
+    assert_eq!(state.offset(), 0);
+    assert!(state.seek_to(end));
+    assert_eq!(state.next(), None);
+}
+
+#[test]
+fn token_state_error_test_0() {
+    let p = operand().then(eq(Tok::Op('+'))).then(operand());
+    let mut state = TokenState::new(lex("a + *"));
+    assert_eq!(p.parse(&mut state).unwrap_err().pos().span(), Span::new(4, 5));
+    let mut state = TokenState::with_end(lex("a +  "), 5);
+    assert_eq!(p.parse(&mut state).unwrap_err().pos().span(), Span::new(5, 5));
+}
+
+#[test]
+fn token_state_error_test_1() {
+    // eq and one_of blame the token they turned down, like satisfy does.
+    let mut state = TokenState::new(lex("a * b"));
+    let p = operand().then(eq(Tok::Op('+')));
+    assert_eq!(p.parse(&mut state).unwrap_err().pos().span(), Span::new(2, 3));
+    let mut state = TokenState::new(lex("a * b"));
+    let p = operand().then(one_of(vec![Tok::Op('+'), Tok::Op('(')]));
+    let err = p.parse(&mut state).unwrap_err();
+    assert_eq!(err.pos().span(), Span::new(2, 3));
+    assert!(err.description().contains("at 2..3"));
+    // At the end of the input it is the empty end span.
+    let mut state = TokenState::with_end(lex("a "), 2);
+    assert_eq!(operand().then(eq(Tok::Op('+'))).parse(&mut state).unwrap_err().pos().span(), Span::new(2, 2));
+}
+
+#[test]
+fn token_spanned_test_0() {
+    // A spanned value ends at the end of its last token, not at the start of the next one.
+    let mut state = TokenState::new(lex("x + 12 * y"));
+    let p = spanned(operand().then(eq(Tok::Op('+'))).then(operand()));
+    let (_, span) = p.parse(&mut state).unwrap();
+    assert_eq!(span.source(), Span::new(0, 6));
+    assert_eq!((span.start.offset(), span.end.offset()), (0, 3));
+    let (_, span) = spanned(many(eq(Tok::Op('+')))).parse(&mut state).unwrap();
+    assert_eq!(span.source(), Span::new(7, 8));
+    assert!(span.is_empty());
+}
+
+#[test]
+fn token_zero_width_test_0() {
+    // Tokens a layout lexer inserts take no source, so only the ordinal tells them apart.
+    let tokens = vec![(Tok::Name(String::from("x")), Span::new(0, 1)),
+                      (Tok::Op('('), Span::new(1, 1)), (Tok::Op(')'), Span::new(1, 1))];
+    let mut state = TokenState::new(tokens);
+    assert!(operand().parse(&mut state).is_ok());
+    let open = state.pos();
+    assert_eq!(eq(Tok::Op('(')).parse(&mut state).unwrap(), Tok::Op('('));
+    let close = state.pos();
+    assert_eq!(open.span(), close.span());
+    assert!(open != close);
+    assert!(open < close);
+    assert!(state.seek_to(open));
+    assert_eq!(state.offset(), 1);
+    assert!(state.seek_to(close));
+    assert_eq!(state.next(), Some(Tok::Op(')')));
+}
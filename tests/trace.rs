@@ -0,0 +1,110 @@
+extern crate ruskell;
+use ruskell::parsec::{VecState, State, Parsec, Monad};
+use ruskell::parsec::atom::{eq, one_of, satisfy};
+use ruskell::parsec::combinator::{try, either, many1};
+use ruskell::parsec::span::Span;
+use ruskell::parsec::token::TokenState;
+use ruskell::parsec::trace::{TraceState, Event, trace, set_global, is_global};
+use std::env;
+use std::process::Command;
+use std::iter::FromIterator;
+
+#[test]
+fn trace_test_0() {
+    let digits = trace("digits", many1(one_of('0'..='9')));
+    let sign = trace("sign", eq('-'));
+    let number = trace("number", either(try(sign.then(digits.clone())), digits));
+    let mut state = TraceState::new(VecState::from_iter("12a".chars()));
+    let re = number.parse(&mut state);
+    assert_eq!(re.unwrap(), vec!['1', '2']);
+    assert_eq!(state.log().render(), "\
+> number at 0
+  > sign at 0
+  < sign failed at 1 consumed \"1\": expect - equal element 1 at 0
+  > digits at 0
+  < digits ok at 2 consumed \"12\"
+< number ok at 2 consumed \"12\"
+");
+}
+
+#[test]
+fn trace_test_1() {
+    let ab = trace("ab", eq('a').then(eq('b')));
+    let mut state = TraceState::new(VecState::from_iter("ac".chars()));
+    assert!(ab.parse(&mut state).is_err());
+    let events = state.log().events();
+    assert_eq!(events.len(), 2);
+    match events[1] {
+        Event::Exit{ref consumed, ok, pos, ..} => {
+            assert_eq!(consumed, "ac");
+            assert!(!ok);
+            assert_eq!(pos, 2);
+        }
+        _ => panic!("expect exit event"),
+    }
+    assert_eq!(state.log().to_json(), "[\
+{\"event\":\"enter\",\"rule\":\"ab\",\"depth\":0,\"pos\":0},\
+{\"event\":\"exit\",\"rule\":\"ab\",\"depth\":0,\"pos\":2,\"ok\":false,\"consumed\":\"ac\",\
+\"message\":\"expect b equal element c at 1\"}]");
+}
+
+#[test]
+fn trace_test_2() {
+    let a = trace("a", eq('a'));
+    let mut state = VecState::from_iter("a".chars());
+    assert_eq!(a.parse(&mut state).unwrap(), 'a');
+    assert_eq!(state.pos(), 1);
+}
+
+#[test]
+fn trace_token_test_0() {
+    // Positions are those of the token state, and the tokens satisfy turned down show up.
+    let tokens = vec![('a', Span::new(0, 1)), ('b', Span::new(2, 3))];
+    let word = trace("word", many1(satisfy(|c:&char| *c == 'a')).then(satisfy(|c:&char| *c == 'c')));
+    let mut state = TraceState::new(TokenState::new(tokens));
+    assert!(word.parse(&mut state).is_err());
+    assert_eq!(state.log().render(), "\
+> word at TokenPos { index: 0, span: Span { start: 0, end: 1 } }
+  ! rejected \"b\" at TokenPos { index: 1, span: Span { start: 2, end: 3 } }
+  ! rejected \"b\" at TokenPos { index: 1, span: Span { start: 2, end: 3 } }
+< word failed at TokenPos { index: 1, span: Span { start: 2, end: 3 } } consumed \"a\": predicate failed
+");
+    match state.log().events()[1] {
+        Event::Reject{depth:1, ref pos, ref item} => {
+            assert_eq!((pos.offset(), pos.span()), (1, Span::new(2, 3)));
+            assert_eq!(item, "b");
+        }
+        _ => panic!("expect reject event"),
+    }
+    // Positions that are not numbers go to JSON as their Debug form.
+    assert!(state.log().to_json().starts_with(
+        "[{\"event\":\"enter\",\"rule\":\"word\",\"depth\":0,\"pos\":\"TokenPos { index: 0, span: Span { start: 0, end: 1 } }\"}"));
+}
+
+// Global mode writes to stderr for the whole process, so it runs in a child copy of this test
+// binary and the parent reads what the child printed.
+#[test]
+fn trace_global_test_0() {
+    if env::var("RUSKELL_TRACE_GLOBAL").is_ok() {
+        set_global(true);
+        assert!(is_global());
+        let ab = trace("ab", trace("a", eq('a')).then(trace("b", eq('b'))));
+        let mut state = VecState::from_iter("ab".chars());
+        assert_eq!(ab.parse(&mut state).unwrap(), 'b');
+        set_global(false);
+        return;
+    }
+    let output = Command::new(env::current_exe().unwrap())
+        .args(&["trace_global_test_0", "--exact", "--nocapture", "--test-threads=1"])
+        .env("RUSKELL_TRACE_GLOBAL", "1")
+        .output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "\
+> ab at 0
+  > a at 0
+  < a ok at 1
+  > b at 1
+  < b ok at 2
+< ab ok at 2
+");
+}
@@ -0,0 +1,11 @@
+#[macro_use]
+extern crate ruskell_derive;
+extern crate ruskell;
+
+peg! {
+    mod keyword r#"
+        type <- 'x'
+    "#
+}
+
+fn main() {}
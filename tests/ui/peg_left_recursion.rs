@@ -0,0 +1,11 @@
+#[macro_use]
+extern crate ruskell_derive;
+extern crate ruskell;
+
+peg! {
+    mod sum r#"
+        sum <- sum '+' [0-9] / [0-9]
+    "#
+}
+
+fn main() {}